@@ -6,8 +6,4 @@ def day04_p1(puzzle_input: str) -> int:
 
 
 def day04_p2(puzzle_input: str) -> int:
-    room = StorageRoom(puzzle_input)
-    result = 0
-    while removed := room.remove_accessible_rolls():
-        result += len(removed)
-    return result
+    return sum(StorageRoom(puzzle_input).remove_until_stable().removed_per_wave)
@@ -2,16 +2,8 @@ from aoc_2025.rs.day07 import TachyonManifold
 
 
 def day07_p1(puzzle_input: str) -> int:
-    manifold = TachyonManifold(puzzle_input)
-    result = 0
-    while (splits := manifold.propagate(combine_beams=True)) is not None:
-        result += splits
-    return result
+    return TachyonManifold(puzzle_input).total_splits()
 
 
 def day07_p2(puzzle_input: str) -> int:
-    manifold = TachyonManifold(puzzle_input)
-    result = 1
-    while (splits := manifold.propagate(combine_beams=False)) is not None:
-        result += splits
-    return result
+    return TachyonManifold(puzzle_input).timeline_count()
@@ -1,4 +1,8 @@
-use pyo3::prelude::*;
+use std::{collections::HashSet, fmt::Display};
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyInt};
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 
 #[pymodule(module = "aoc_2025.rs.day03")]
@@ -8,6 +12,25 @@ pub fn day3(m: &Bound<'_, PyModule>) -> PyResult<()> {
     Ok(())
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum BatteryBankError {
+    InvalidPrefix(char),
+}
+
+impl Display for BatteryBankError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPrefix(c) => write!(f, "Prefix must be digits, but found {c:?}"),
+        }
+    }
+}
+
+impl From<BatteryBankError> for PyErr {
+    fn from(value: BatteryBankError) -> Self {
+        PyValueError::new_err(value.to_string())
+    }
+}
+
 #[gen_stub_pyclass]
 #[pyclass(module = "aoc_2025.rs.day03")]
 struct BatteryBank {
@@ -25,6 +48,12 @@ impl From<&str> for BatteryBank {
     }
 }
 
+/// First index a battery can be selected at after selecting the one at
+/// ``index``, saturating rather than overflowing for huge gaps
+fn after_gap(index: usize, min_gap: usize) -> usize {
+    index.saturating_add(min_gap).saturating_add(1)
+}
+
 impl BatteryBank {
     /// Get (index, value) of the first instance of the maximum value in a
     /// section of the battery bank.
@@ -39,6 +68,89 @@ impl BatteryBank {
             .unwrap();
         (from + ind, max)
     }
+
+    /// For each start position and number of batteries still to select,
+    /// whether a valid selection exists using only batteries from that
+    /// position onwards.
+    ///
+    /// Indexed as ``[start][count]``. Starts past the end of the bank are
+    /// clamped to the end, where only an empty selection is possible.
+    fn feasible_selections(
+        &self,
+        using: usize,
+        min_gap: usize,
+        disabled: &HashSet<usize>,
+    ) -> Vec<Vec<bool>> {
+        let len = self.batteries.len();
+        let mut feasible = vec![vec![false; using + 1]; len + 1];
+        feasible[len][0] = true;
+
+        for start in (0..len).rev() {
+            feasible[start][0] = true;
+            for count in 1..=using {
+                let skip = feasible[start + 1][count];
+                let take = !disabled.contains(&start)
+                    && feasible[after_gap(start, min_gap).min(len)][count - 1];
+                feasible[start][count] = skip || take;
+            }
+        }
+
+        feasible
+    }
+
+    /// Max joltage and chosen indices under wiring constraints, with the
+    /// joltage unbounded since it has as many digits as batteries used
+    fn constrained_joltage(
+        &self,
+        using: usize,
+        min_gap: Option<usize>,
+        disabled: Option<HashSet<usize>>,
+        prefix: Option<&str>,
+    ) -> Result<Option<(BigUint, Vec<usize>)>, BatteryBankError> {
+        let min_gap = min_gap.unwrap_or(0);
+        let disabled = disabled.unwrap_or_default();
+        let prefix = prefix
+            .unwrap_or_default()
+            .chars()
+            .map(|c| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or(BatteryBankError::InvalidPrefix(c))
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        if prefix.len() > using {
+            return Ok(None);
+        }
+
+        let len = self.batteries.len();
+        let feasible = self.feasible_selections(using, min_gap, &disabled);
+
+        let mut start = 0;
+        let mut joltage = BigUint::zero();
+        let mut indices = Vec::with_capacity(using);
+
+        for step in 0..using {
+            let remaining = using - step - 1;
+            // Feasibility only shrinks as the start moves right, so the first
+            // instance of the best value always leaves the most options open
+            let Some((index, value)) = (start..len)
+                .filter(|&i| {
+                    !disabled.contains(&i) && feasible[after_gap(i, min_gap).min(len)][remaining]
+                })
+                .map(|i| (i, self.batteries[i]))
+                .filter(|&(_, v)| prefix.get(step).is_none_or(|&p| v == p))
+                .reduce(|best, next| if next.1 > best.1 { next } else { best })
+            else {
+                return Ok(None);
+            };
+
+            joltage = joltage * 10u32 + value;
+            indices.push(index);
+            start = after_gap(index, min_gap);
+        }
+
+        Ok(Some((joltage, indices)))
+    }
 }
 
 #[gen_stub_pymethods]
@@ -52,18 +164,38 @@ impl BatteryBank {
     /// Find the max joltage of the battery bank using the given number of batteries
     fn joltage(&self, using: usize) -> usize {
         let mut start = 0;
-        let mut end = self.batteries.len() - using + 1;
         let mut result = String::new();
 
-        for _ in 0..using {
+        for end in (self.batteries.len() - using + 1)..=self.batteries.len() {
             let (index, max) = self.max_of_section(start, end);
             start = index + 1;
-            end += 1;
             result += &max.to_string();
         }
 
         result.parse().unwrap()
     }
+
+    /// Find the max joltage of the battery bank under wiring constraints,
+    /// returning the joltage and the indices of the chosen batteries, or None
+    /// if no selection satisfies the constraints.
+    ///
+    /// ``min_gap`` is the minimum number of unselected batteries between any
+    /// two selected ones, batteries at ``disabled`` positions can't be
+    /// selected, and the first selected batteries must match the digits of
+    /// ``prefix``.
+    #[pyo3(name = "constrained_joltage", signature = (using, min_gap=None, disabled=None, prefix=None))]
+    fn py_constrained_joltage<'py>(
+        &self,
+        py: Python<'py>,
+        using: usize,
+        min_gap: Option<usize>,
+        disabled: Option<HashSet<usize>>,
+        prefix: Option<&str>,
+    ) -> PyResult<Option<(Bound<'py, PyInt>, Vec<usize>)>> {
+        self.constrained_joltage(using, min_gap, disabled, prefix)?
+            .map(|(joltage, indices)| Ok((joltage.into_pyobject(py)?, indices)))
+            .transpose()
+    }
 }
 
 #[cfg(test)]
@@ -108,4 +240,87 @@ mod tests {
     fn test_joltage_12(#[case] input: &str, #[case] expected: usize) {
         assert_eq!(BatteryBank::from(input).joltage(12), expected)
     }
+
+    #[rstest]
+    #[case("987654321111111", 2)]
+    #[case("818181911112111", 2)]
+    #[case("8781", 2)]
+    #[case("987654321111111", 12)]
+    #[case("234234234234278", 12)]
+    #[case("818181911112111", 12)]
+    fn test_constrained_joltage_unconstrained(#[case] input: &str, #[case] using: usize) {
+        let bank = BatteryBank::from(input);
+        let (joltage, _) = bank
+            .constrained_joltage(using, None, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(joltage, BigUint::from(bank.joltage(using)));
+    }
+
+    #[rstest]
+    #[case("987654321111111", 2, 0, vec![], "", Some((98, vec![0, 1])))]
+    #[case("987654321111111", 2, 1, vec![], "", Some((97, vec![0, 2])))]
+    #[case("987654321111111", 3, 2, vec![], "", Some((963, vec![0, 3, 6])))]
+    #[case("987654321111111", 2, 0, vec![0], "", Some((87, vec![1, 2])))]
+    #[case("987654321111111", 2, 0, vec![], "7", Some((76, vec![2, 3])))]
+    #[case("818181911112111", 3, 0, vec![6], "1", Some((188, vec![1, 2, 4])))]
+    #[case("818181911112111", 3, 1, vec![6], "1", Some((182, vec![1, 4, 11])))]
+    #[case("8781", 2, 2, vec![], "", Some((81, vec![0, 3])))]
+    #[case("8781", 2, 3, vec![], "", None)]
+    #[case("8781", 2, 0, vec![], "9", None)]
+    #[case("8781", 1, 0, vec![0, 1, 2, 3], "", None)]
+    fn test_constrained_joltage(
+        #[case] input: &str,
+        #[case] using: usize,
+        #[case] min_gap: usize,
+        #[case] disabled: Vec<usize>,
+        #[case] prefix: &str,
+        #[case] expected: Option<(u32, Vec<usize>)>,
+    ) {
+        assert_eq!(
+            BatteryBank::from(input).constrained_joltage(
+                using,
+                Some(min_gap),
+                Some(disabled.into_iter().collect()),
+                Some(prefix)
+            ),
+            Ok(expected.map(|(joltage, indices)| (BigUint::from(joltage), indices)))
+        );
+    }
+
+    #[rstest]
+    fn test_constrained_joltage_large() {
+        let bank = BatteryBank::from("9".repeat(30).as_str());
+        let (joltage, indices) = bank
+            .constrained_joltage(25, None, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(joltage.to_string(), "9".repeat(25));
+        assert_eq!(indices, (0..25).collect::<Vec<_>>());
+    }
+
+    #[rstest]
+    fn test_constrained_joltage_huge_gap() {
+        let bank = BatteryBank::from("987654321111111");
+        assert_eq!(
+            bank.constrained_joltage(1, Some(usize::MAX), None, None),
+            Ok(Some((BigUint::from(9u32), vec![0])))
+        );
+        assert_eq!(
+            bank.constrained_joltage(2, Some(usize::MAX), None, None),
+            Ok(None)
+        );
+    }
+
+    #[rstest]
+    #[case("7a")]
+    #[case("-1")]
+    fn test_constrained_joltage_invalid_prefix(#[case] prefix: &str) {
+        let bank = BatteryBank::from("987654321111111");
+        let invalid = prefix.chars().find(|c| !c.is_ascii_digit()).unwrap();
+        assert_eq!(
+            bank.constrained_joltage(2, None, None, Some(prefix)),
+            Err(BatteryBankError::InvalidPrefix(invalid))
+        );
+    }
 }
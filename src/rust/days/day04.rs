@@ -1,73 +1,618 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::PathBuf,
+};
 
-use pyo3::prelude::*;
-use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+
+use crate::pnm;
+use bit_grid::BitGrid;
+
+mod bit_grid;
 
 #[pymodule(module = "aoc_2025.rs.day04")]
 pub fn day4(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<StorageRoom>()?;
+    m.add_class::<RemovalHistory>()?;
+    m.add_class::<Neighbourhood>()?;
+    m.add_class::<Comparison>()?;
+    m.add_class::<Backend>()?;
+    m.add_class::<EdgeMode>()?;
 
     Ok(())
 }
 
+#[derive(PartialEq, Debug)]
+enum StorageRoomError {
+    MissingOffsets,
+    UnexpectedOffsets,
+    OutOfBounds((isize, isize)),
+    /// Neighbours would wrap round a torus onto each other or onto the roll
+    /// itself, so would be counted more than once
+    TorusTooSmall((usize, usize)),
+}
+
+impl Display for StorageRoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingOffsets => write!(f, "Custom neighbourhoods require a list of offsets"),
+            Self::UnexpectedOffsets => {
+                write!(f, "Offsets can only be given for custom neighbourhoods")
+            }
+            Self::OutOfBounds(pos) => write!(f, "Position {pos:?} is outside the room"),
+            Self::TorusTooSmall((rows, cols)) => write!(
+                f,
+                "A {rows}x{cols} torus is too small for the neighbourhood to wrap round"
+            ),
+        }
+    }
+}
+
+impl From<StorageRoomError> for PyErr {
+    fn from(value: StorageRoomError) -> Self {
+        PyValueError::new_err(value.to_string())
+    }
+}
+
+/// Shape of the area around a roll in which other rolls count as neighbours
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day04")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Neighbourhood {
+    /// Every cell within the radius, including diagonals
+    Moore,
+    /// Cells within the radius by Manhattan distance
+    VonNeumann,
+    /// Cells within the radius on a hex grid in axial (row, col) coordinates
+    Hexagonal,
+    /// An explicit list of (row, col) offsets
+    Custom,
+}
+
+impl Neighbourhood {
+    /// Get the (row, col) offsets of neighbours in this neighbourhood
+    fn offsets(
+        self,
+        radius: isize,
+        custom: Option<Vec<(isize, isize)>>,
+    ) -> Result<Vec<(isize, isize)>, StorageRoomError> {
+        let in_neighbourhood: fn(isize, isize, isize) -> bool = match (self, custom) {
+            (Self::Custom, Some(offsets)) => return Ok(offsets),
+            (Self::Custom, None) => return Err(StorageRoomError::MissingOffsets),
+            (_, Some(_)) => return Err(StorageRoomError::UnexpectedOffsets),
+            (Self::Moore, None) => |dr, dc, r| dr.abs().max(dc.abs()) <= r,
+            (Self::VonNeumann, None) => |dr, dc, r| dr.abs() + dc.abs() <= r,
+            (Self::Hexagonal, None) => |dr, dc, r| (dr.abs() + dc.abs() + (dr + dc).abs()) / 2 <= r,
+        };
+
+        Ok((-radius..=radius)
+            .flat_map(|dr| (-radius..=radius).map(move |dc| (dr, dc)))
+            .filter(|&offset| offset != (0, 0))
+            .filter(|&(dr, dc)| in_neighbourhood(dr, dc, radius))
+            .collect())
+    }
+}
+
+/// How a roll's neighbour count is compared with the threshold to decide
+/// whether a forklift can access it
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day04")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Comparison {
+    LessThan,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    GreaterThan,
+}
+
+impl Comparison {
+    fn holds(self, count: usize, threshold: usize) -> bool {
+        match self {
+            Self::LessThan => count < threshold,
+            Self::LessOrEqual => count <= threshold,
+            Self::Equal => count == threshold,
+            Self::GreaterOrEqual => count >= threshold,
+            Self::GreaterThan => count > threshold,
+        }
+    }
+}
+
+/// How the positions of rolls in a room are stored
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day04")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Backend {
+    /// A hash set of positions, suited to sparse rooms
+    Sparse,
+    /// A bitset over the whole room with neighbour counts updated on removal,
+    /// suited to large dense rooms
+    Dense,
+}
+
+/// How cells beyond the edges of the room are treated
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day04")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum EdgeMode {
+    /// The room is surrounded by empty space
+    Open,
+    /// The room wraps around, so each edge neighbours the opposite edge. The
+    /// room must be big enough that no neighbourhood wraps onto itself.
+    Torus,
+    /// The room is surrounded by walls, which count as occupied neighbours
+    Walls,
+}
+
+#[derive(PartialEq, Debug)]
+enum Rolls {
+    Sparse(HashSet<(isize, isize)>),
+    Dense(BitGrid),
+}
+
 #[gen_stub_pyclass]
 #[pyclass(module = "aoc_2025.rs.day04")]
 struct StorageRoom {
-    #[pyo3(get)]
-    /// Positions of rolls currently in the room
-    rolls: HashSet<(isize, isize)>,
+    rolls: Rolls,
+
+    /// (rows, cols) of the room as parsed
+    dimensions: (usize, usize),
+    /// (row, col) offsets of the cells whose rolls count as neighbours
+    offsets: Vec<(isize, isize)>,
+    /// Neighbour count that a roll is compared against to be accessible
+    threshold: usize,
+    /// How the neighbour count is compared against the threshold
+    comparison: Comparison,
+    /// How cells beyond the edges of the room are treated
+    edges: EdgeMode,
 }
 
-impl From<&str> for StorageRoom {
-    fn from(value: &str) -> Self {
+/// Options for how a room is stored and which of its rolls are accessible
+#[derive(Clone)]
+struct RoomOptions {
+    neighbourhood: Neighbourhood,
+    radius: isize,
+    offsets: Option<Vec<(isize, isize)>>,
+    threshold: usize,
+    comparison: Comparison,
+    backend: Backend,
+    edges: EdgeMode,
+}
+
+impl Default for RoomOptions {
+    fn default() -> Self {
         Self {
-            rolls: value
-                .lines()
-                .enumerate()
-                .flat_map(|(row_index, row)| {
-                    row.char_indices()
-                        .filter(|&(_, char)| char == '@')
-                        .map(move |(col_index, _)| (row_index as isize, col_index as isize))
-                })
-                .collect(),
+            neighbourhood: Neighbourhood::Moore,
+            radius: 1,
+            offsets: None,
+            threshold: 4,
+            comparison: Comparison::LessThan,
+            backend: Backend::Sparse,
+            edges: EdgeMode::Open,
         }
     }
 }
 
-impl StorageRoom {
-    fn neighbour_count(&self, (row, col): (isize, isize)) -> usize {
-        [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .map(|(dr, dc)| (row + dr, col + dc))
+/// Get the (rows, cols) of the room's text
+fn parse_dimensions(value: &str) -> (usize, usize) {
+    (
+        value.lines().count(),
+        value
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0),
+    )
+}
+
+/// Parse the positions of rolls from the room's text
+fn parse_rolls(value: &str) -> HashSet<(isize, isize)> {
+    value
+        .lines()
+        .enumerate()
+        .flat_map(|(row_index, row)| {
+            row.char_indices()
+                .filter(|&(_, char)| char == '@')
+                .map(move |(col_index, _)| (row_index as isize, col_index as isize))
+        })
+        .collect()
+}
+
+/// Whether a torus of the given (rows, cols) is big enough that every
+/// neighbour of a roll is a different cell, and none of them the roll itself
+fn fits_torus((rows, cols): (usize, usize), offsets: &[(isize, isize)]) -> bool {
+    if rows == 0 || cols == 0 {
+        return false;
+    }
+    let cells: HashSet<(isize, isize)> = offsets
         .iter()
-        .filter(|&n| self.rolls.contains(n))
-        .count()
+        .chain([&(0, 0)])
+        .map(|&(dr, dc)| (dr.rem_euclid(rows as isize), dc.rem_euclid(cols as isize)))
+        .collect();
+    cells.len() == offsets.len() + 1
+}
+
+impl From<&str> for StorageRoom {
+    fn from(value: &str) -> Self {
+        Self::with_options(value, RoomOptions::default()).unwrap()
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day04")]
+#[derive(PartialEq, Debug)]
+struct RemovalHistory {
+    #[pyo3(get)]
+    /// Number of waves of removals before the room became stable
+    waves: usize,
+
+    #[pyo3(get)]
+    /// Number of rolls removed in each wave
+    removed_per_wave: Vec<usize>,
+
+    #[pyo3(get)]
+    /// Positions of removed rolls to the (zero-based) wave they were removed in
+    removal_wave: HashMap<(isize, isize), usize>,
+
+    /// (rows, cols) of the room the rolls were removed from
+    dimensions: (usize, usize),
+    /// Positions of rolls left once the room became stable
+    remaining: HashSet<(isize, isize)>,
+}
+
+impl RemovalHistory {
+    /// All positions in the room, in row-major order
+    fn cells(&self) -> impl Iterator<Item = (isize, isize)> {
+        let (rows, cols) = self.dimensions;
+        (0..rows as isize).flat_map(move |row| (0..cols as isize).map(move |col| (row, col)))
+    }
+
+    /// Greyscale level of each cell in row-major order. Empty cells are black,
+    /// rolls that were never removed are white, and removed rolls are lighter
+    /// the later they were removed.
+    fn wave_map_levels(&self) -> Vec<u8> {
+        self.cells()
+            .map(|pos| match self.removal_wave.get(&pos) {
+                Some(&wave) => (48 + 160 * wave / self.waves) as u8,
+                None if self.remaining.contains(&pos) => 255,
+                None => 0,
+            })
+            .collect()
+    }
+
+    /// Colour of each cell in row-major order. Empty cells are black, rolls
+    /// that were never removed are white, and removed rolls fade from blue to
+    /// red the later they were removed.
+    fn wave_map_colours(&self) -> Vec<[u8; 3]> {
+        self.cells()
+            .map(|pos| match self.removal_wave.get(&pos) {
+                Some(&wave) => {
+                    let red = (255 * (wave + 1) / self.waves) as u8;
+                    [red, 64, 255 - red]
+                }
+                None if self.remaining.contains(&pos) => [255, 255, 255],
+                None => [0, 0, 0],
+            })
+            .collect()
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl RemovalHistory {
+    /// Save the wave in which each roll was removed as a greyscale PGM image
+    fn save_pgm(&self, path: PathBuf) -> PyResult<()> {
+        let (rows, cols) = self.dimensions;
+        Ok(pnm::write_pgm(&path, cols, rows, &self.wave_map_levels())?)
+    }
+
+    /// Save the wave in which each roll was removed as a colour PPM image
+    fn save_ppm(&self, path: PathBuf) -> PyResult<()> {
+        let (rows, cols) = self.dimensions;
+        Ok(pnm::write_ppm(&path, cols, rows, &self.wave_map_colours())?)
+    }
+}
+
+impl StorageRoom {
+    fn with_options(rolls: &str, options: RoomOptions) -> Result<Self, StorageRoomError> {
+        let dimensions = parse_dimensions(rolls);
+        let offsets = options
+            .neighbourhood
+            .offsets(options.radius, options.offsets)?;
+        if options.edges == EdgeMode::Torus && !fits_torus(dimensions, &offsets) {
+            return Err(StorageRoomError::TorusTooSmall(dimensions));
+        }
+        let positions = parse_rolls(rolls);
+
+        Ok(Self {
+            rolls: match options.backend {
+                Backend::Sparse => Rolls::Sparse(positions),
+                Backend::Dense => {
+                    Rolls::Dense(BitGrid::new(dimensions, &offsets, options.edges, positions))
+                }
+            },
+            dimensions,
+            offsets,
+            threshold: options.threshold,
+            comparison: options.comparison,
+            edges: options.edges,
+        })
+    }
+
+    fn contains(&self, pos: (isize, isize)) -> bool {
+        match &self.rolls {
+            Rolls::Sparse(rolls) => rolls.contains(&pos),
+            Rolls::Dense(grid) => grid.contains(pos),
+        }
+    }
+
+    /// Whether a position is in the room as parsed, which is where rolls can
+    /// be placed whatever the backend
+    fn in_bounds(&self, (row, col): (isize, isize)) -> bool {
+        let (rows, cols) = self.dimensions;
+        (0..rows as isize).contains(&row) && (0..cols as isize).contains(&col)
+    }
+
+    /// Get the position a cell refers to under the room's edge mode, or None
+    /// if the cell is a wall
+    fn resolve(&self, (row, col): (isize, isize)) -> Option<(isize, isize)> {
+        let (rows, cols) = self.dimensions;
+        match self.edges {
+            EdgeMode::Open => Some((row, col)),
+            EdgeMode::Torus => Some((row.rem_euclid(rows as isize), col.rem_euclid(cols as isize))),
+            EdgeMode::Walls => self.in_bounds((row, col)).then_some((row, col)),
+        }
+    }
+
+    /// Add a roll, returning whether there wasn't one there already
+    fn put(&mut self, pos: (isize, isize)) -> Result<bool, StorageRoomError> {
+        if !self.in_bounds(pos) {
+            return Err(StorageRoomError::OutOfBounds(pos));
+        }
+        Ok(match &mut self.rolls {
+            Rolls::Sparse(rolls) => rolls.insert(pos),
+            Rolls::Dense(grid) => grid.insert(pos),
+        })
+    }
+
+    /// Add several rolls, returning how many weren't there already. No rolls
+    /// are added if any can't be placed.
+    fn put_all(&mut self, positions: &[(isize, isize)]) -> Result<usize, StorageRoomError> {
+        if let Some(&pos) = positions.iter().find(|&&pos| !self.in_bounds(pos)) {
+            return Err(StorageRoomError::OutOfBounds(pos));
+        }
+        positions
+            .iter()
+            .try_fold(0, |added, &pos| Ok(added + self.put(pos)? as usize))
+    }
+
+    /// Remove a roll, returning whether there was one to remove
+    fn take(&mut self, pos: (isize, isize)) -> bool {
+        match &mut self.rolls {
+            Rolls::Sparse(rolls) => rolls.remove(&pos),
+            Rolls::Dense(grid) => grid.remove(pos),
+        }
+    }
+
+    fn positions(&self) -> Box<dyn Iterator<Item = (isize, isize)> + '_> {
+        match &self.rolls {
+            Rolls::Sparse(rolls) => Box::new(rolls.iter().cloned()),
+            Rolls::Dense(grid) => Box::new(grid.iter()),
+        }
+    }
+
+    /// Positions whose rolls count as neighbours of the given position, with
+    /// None for neighbouring walls
+    fn neighbours(
+        &self,
+        (row, col): (isize, isize),
+    ) -> impl Iterator<Item = Option<(isize, isize)>> + '_ {
+        self.offsets
+            .iter()
+            .map(move |&(dr, dc)| self.resolve((row + dr, col + dc)))
+    }
+
+    /// Positions that count the given position as one of their neighbours.
+    ///
+    /// This is the same as ``neighbours`` unless a custom neighbourhood is
+    /// asymmetric.
+    fn dependents(&self, (row, col): (isize, isize)) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.offsets
+            .iter()
+            .filter_map(move |&(dr, dc)| self.resolve((row - dr, col - dc)))
+    }
+
+    fn neighbour_count(&self, pos: (isize, isize)) -> usize {
+        match &self.rolls {
+            Rolls::Sparse(rolls) => self
+                .neighbours(pos)
+                .filter(|n| n.is_none_or(|n| rolls.contains(&n)))
+                .count(),
+            Rolls::Dense(grid) => grid.neighbour_count(pos),
+        }
+    }
+
+    fn is_accessible(&self, neighbour_count: usize) -> bool {
+        self.comparison.holds(neighbour_count, self.threshold)
+    }
+
+    /// Get the groups of rolls that are connected through the neighbourhood,
+    /// sorted in descending size order
+    fn components(&self) -> Vec<HashSet<(isize, isize)>> {
+        let mut starts: Vec<(isize, isize)> = self.positions().collect();
+        starts.sort();
+        let mut unvisited: HashSet<(isize, isize)> = starts.iter().cloned().collect();
+        let mut components = Vec::new();
+
+        for start in starts {
+            if !unvisited.remove(&start) {
+                continue;
+            }
+            let mut component = HashSet::from([start]);
+            let mut to_visit = vec![start];
+
+            while let Some(pos) = to_visit.pop() {
+                for adjacent in self.neighbours(pos).flatten().chain(self.dependents(pos)) {
+                    if unvisited.remove(&adjacent) {
+                        component.insert(adjacent);
+                        to_visit.push(adjacent);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+
+        components
     }
 }
 
 #[gen_stub_pymethods]
 #[pymethods]
 impl StorageRoom {
+    /// Create a storage room from its text representation.
+    ///
+    /// By default a roll is accessible if fewer than 4 of its 8 adjacent cells
+    /// hold rolls. ``offsets`` must be given for, and only for, a custom
+    /// neighbourhood, which ignores ``radius``. Rolls are stored sparsely
+    /// unless another ``backend`` is chosen, and the room is surrounded by
+    /// empty space unless another mode is chosen for its ``edges``.
     #[new]
-    fn new(rolls: &str) -> Self {
-        Self::from(rolls)
+    #[pyo3(signature = (rolls, neighbourhood=None, radius=None, offsets=None, threshold=None, comparison=None, backend=None, edges=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        rolls: &str,
+        neighbourhood: Option<Neighbourhood>,
+        radius: Option<isize>,
+        offsets: Option<Vec<(isize, isize)>>,
+        threshold: Option<usize>,
+        comparison: Option<Comparison>,
+        backend: Option<Backend>,
+        edges: Option<EdgeMode>,
+    ) -> PyResult<Self> {
+        let defaults = RoomOptions::default();
+        Ok(Self::with_options(
+            rolls,
+            RoomOptions {
+                neighbourhood: neighbourhood.unwrap_or(defaults.neighbourhood),
+                radius: radius.unwrap_or(defaults.radius),
+                offsets,
+                threshold: threshold.unwrap_or(defaults.threshold),
+                comparison: comparison.unwrap_or(defaults.comparison),
+                backend: backend.unwrap_or(defaults.backend),
+                edges: edges.unwrap_or(defaults.edges),
+            },
+        )?)
+    }
+
+    /// (rows, cols) of the room as parsed
+    #[getter]
+    fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    /// Positions of rolls currently in the room
+    #[getter]
+    fn rolls(&self) -> HashSet<(isize, isize)> {
+        self.positions().collect()
+    }
+
+    /// (row, col) coordinates of the top-left and bottom-right rolls of the
+    /// smallest rectangle containing every roll, or None if the room is empty
+    #[getter]
+    fn bounding_box(&self) -> Option<((isize, isize), (isize, isize))> {
+        self.positions()
+            .fold(None, |bounds, (row, col)| match bounds {
+                None => Some(((row, col), (row, col))),
+                Some(((min_row, min_col), (max_row, max_col))) => Some((
+                    (min_row.min(row), min_col.min(col)),
+                    (max_row.max(row), max_col.max(col)),
+                )),
+            })
+    }
+
+    /// Get the (row, col) coordinates of rolls within a rectangle, given by
+    /// its inclusive top-left and bottom-right corners
+    fn rolls_in_rect(
+        &self,
+        top_left: (isize, isize),
+        bottom_right: (isize, isize),
+    ) -> HashSet<(isize, isize)> {
+        let ((min_row, min_col), (max_row, max_col)) = (top_left, bottom_right);
+        self.positions()
+            .filter(|&(row, col)| {
+                (min_row..=max_row).contains(&row) && (min_col..=max_col).contains(&col)
+            })
+            .collect()
+    }
+
+    /// Add a roll, returning False if there was already one there. Raises an
+    /// error if the position is outside the room as parsed.
+    fn add_roll(&mut self, pos: (isize, isize)) -> PyResult<bool> {
+        Ok(self.put(pos)?)
+    }
+
+    /// Add rolls, returning the number that weren't there already. Either all
+    /// the rolls are added or, if any are out of bounds, none are.
+    fn add_rolls(&mut self, positions: Vec<(isize, isize)>) -> PyResult<usize> {
+        Ok(self.put_all(&positions)?)
+    }
+
+    /// Remove a roll, returning False if there was no roll there
+    fn remove_roll(&mut self, pos: (isize, isize)) -> bool {
+        self.take(pos)
+    }
+
+    /// Get the groups of rolls that are connected to each other through the
+    /// neighbourhood, sorted in descending size order.
+    ///
+    /// Comparing the components before and after removing rolls shows which
+    /// clusters the removal has isolated.
+    fn connected_components(&self) -> Vec<HashSet<(isize, isize)>> {
+        self.components()
     }
 
-    /// Get the (row, col) coordinates of rolls that have fewer than 4 adjacent
-    /// rolls
+    /// Label each roll with the index of its connected component in
+    /// ``connected_components``
+    fn component_labels(&self) -> HashMap<(isize, isize), usize> {
+        self.components()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(label, component)| component.into_iter().map(move |pos| (pos, label)))
+            .collect()
+    }
+
+    /// Draw the room as text, with ``@`` for rolls, ``x`` for rolls that are
+    /// accessible and ``.`` for empty space
+    fn render(&self) -> String {
+        let accessible = self.accessible_rolls();
+        let (rows, cols) = self.dimensions;
+
+        (0..rows as isize)
+            .map(|row| {
+                (0..cols as isize)
+                    .map(|col| {
+                        if accessible.contains(&(row, col)) {
+                            'x'
+                        } else if self.contains((row, col)) {
+                            '@'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Get the (row, col) coordinates of rolls that are accessible by forklift
     fn accessible_rolls(&self) -> HashSet<(isize, isize)> {
-        self.rolls
-            .iter()
-            .filter(|&&pos| self.neighbour_count(pos) < 4)
-            .cloned()
+        self.positions()
+            .filter(|&pos| self.is_accessible(self.neighbour_count(pos)))
             .collect()
     }
 
@@ -76,12 +621,47 @@ impl StorageRoom {
     fn remove_accessible_rolls(&mut self) -> HashSet<(isize, isize)> {
         let to_remove = self.accessible_rolls();
 
-        to_remove.iter().for_each(|r| {
-            self.rolls.remove(r);
+        to_remove.iter().for_each(|&r| {
+            self.take(r);
         });
 
         to_remove
     }
+
+    /// Repeatedly remove all accessible rolls until none are left accessible,
+    /// returning a history of the removal waves.
+    ///
+    /// Only neighbours of rolls removed in a wave are re-checked for the next
+    /// wave, rather than re-scanning the whole room.
+    fn remove_until_stable(&mut self) -> RemovalHistory {
+        let mut wave = self.accessible_rolls();
+
+        let mut removed_per_wave = Vec::new();
+        let mut removal_wave = HashMap::new();
+
+        while !wave.is_empty() {
+            let wave_index = removed_per_wave.len();
+            wave.iter().for_each(|&pos| {
+                self.take(pos);
+                removal_wave.insert(pos, wave_index);
+            });
+            removed_per_wave.push(wave.len());
+
+            wave = wave
+                .iter()
+                .flat_map(|&pos| self.dependents(pos))
+                .filter(|&pos| self.contains(pos) && self.is_accessible(self.neighbour_count(pos)))
+                .collect();
+        }
+
+        RemovalHistory {
+            waves: removed_per_wave.len(),
+            removed_per_wave,
+            removal_wave,
+            dimensions: self.dimensions,
+            remaining: self.rolls(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -89,12 +669,26 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    const EXAMPLE: &str = include_str!("../../../data/test/d4");
+
+    /// The example room with the given options, stored in the given backend
+    fn example_room(options: &RoomOptions, backend: Backend) -> StorageRoom {
+        StorageRoom::with_options(
+            EXAMPLE,
+            RoomOptions {
+                backend,
+                ..options.clone()
+            },
+        )
+        .unwrap()
+    }
+
     #[rstest]
     fn test_create_storage_room() {
         let example_input = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@";
         let room = StorageRoom::from(example_input);
         assert_eq!(
-            room.rolls,
+            room.rolls(),
             HashSet::from([
                 (0, 2),
                 (0, 3),
@@ -168,7 +762,7 @@ mod tests {
             ])
         );
         assert_eq!(
-            room.rolls,
+            room.rolls(),
             HashSet::from([
                 (0, 7),
                 (1, 1),
@@ -206,4 +800,461 @@ mod tests {
             ])
         )
     }
+
+    #[rstest]
+    fn test_remove_until_stable() {
+        let mut room = StorageRoom::from(EXAMPLE);
+        let mut stepped_room = StorageRoom::from(EXAMPLE);
+
+        let history = room.remove_until_stable();
+
+        let mut expected_removed_per_wave = Vec::new();
+        let mut expected_removal_wave = HashMap::new();
+        loop {
+            let removed = stepped_room.remove_accessible_rolls();
+            if removed.is_empty() {
+                break;
+            }
+            removed.iter().for_each(|&pos| {
+                expected_removal_wave.insert(pos, expected_removed_per_wave.len());
+            });
+            expected_removed_per_wave.push(removed.len());
+        }
+
+        assert_eq!(history.removed_per_wave.iter().sum::<usize>(), 43);
+        assert_eq!(history.removed_per_wave[0], 13);
+        assert_eq!(history.waves, expected_removed_per_wave.len());
+        assert_eq!(history.removed_per_wave, expected_removed_per_wave);
+        assert_eq!(history.removal_wave, expected_removal_wave);
+        assert_eq!(history.remaining, stepped_room.rolls());
+        assert_eq!(room.rolls(), stepped_room.rolls());
+    }
+
+    #[rstest]
+    #[case(Neighbourhood::Moore, 1, 8)]
+    #[case(Neighbourhood::Moore, 2, 24)]
+    #[case(Neighbourhood::VonNeumann, 1, 4)]
+    #[case(Neighbourhood::VonNeumann, 2, 12)]
+    #[case(Neighbourhood::Hexagonal, 1, 6)]
+    #[case(Neighbourhood::Hexagonal, 2, 18)]
+    fn test_neighbourhood_offsets(
+        #[case] neighbourhood: Neighbourhood,
+        #[case] radius: isize,
+        #[case] expected_count: usize,
+    ) {
+        let offsets = neighbourhood.offsets(radius, None).unwrap();
+        assert_eq!(offsets.len(), expected_count);
+        assert!(!offsets.contains(&(0, 0)));
+    }
+
+    #[rstest]
+    fn test_hexagonal_offsets() {
+        assert_eq!(
+            Neighbourhood::Hexagonal
+                .offsets(1, None)
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>(),
+            HashSet::from([(-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0)])
+        );
+    }
+
+    #[rstest]
+    #[case(Neighbourhood::Custom, None, StorageRoomError::MissingOffsets)]
+    #[case(
+        Neighbourhood::Moore,
+        Some(vec![(0, 1)]),
+        StorageRoomError::UnexpectedOffsets
+    )]
+    fn test_neighbourhood_offsets_invalid(
+        #[case] neighbourhood: Neighbourhood,
+        #[case] custom: Option<Vec<(isize, isize)>>,
+        #[case] expected: StorageRoomError,
+    ) {
+        assert_eq!(neighbourhood.offsets(1, custom), Err(expected));
+    }
+
+    #[rstest]
+    fn test_accessible_rolls_von_neumann() {
+        let room = StorageRoom::with_options(
+            ".@.\n@@@\n.@.",
+            RoomOptions {
+                neighbourhood: Neighbourhood::VonNeumann,
+                comparison: Comparison::GreaterOrEqual,
+                backend: Backend::Dense,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(room.accessible_rolls(), HashSet::from([(1, 1)]));
+    }
+
+    #[rstest]
+    #[case(None, Comparison::LessThan, 2)]
+    #[case(None, Comparison::Equal, 1)]
+    #[case(Some(vec![(0, 1)]), Comparison::LessThan, 1)]
+    #[case(Some(vec![(0, 1), (1, 0)]), Comparison::LessOrEqual, 1)]
+    fn test_remove_until_stable_with_rules(
+        #[case] offsets: Option<Vec<(isize, isize)>>,
+        #[case] comparison: Comparison,
+        #[case] threshold: usize,
+    ) {
+        let neighbourhood = if offsets.is_some() {
+            Neighbourhood::Custom
+        } else {
+            Neighbourhood::VonNeumann
+        };
+        let options = RoomOptions {
+            neighbourhood,
+            offsets,
+            threshold,
+            comparison,
+            ..Default::default()
+        };
+        let mut room = example_room(&options, Backend::Sparse);
+        let mut stepped_room = example_room(&options, Backend::Sparse);
+
+        let history = room.remove_until_stable();
+
+        let mut expected_removed_per_wave = Vec::new();
+        loop {
+            let removed = stepped_room.remove_accessible_rolls();
+            if removed.is_empty() {
+                break;
+            }
+            expected_removed_per_wave.push(removed.len());
+        }
+
+        assert_eq!(history.removed_per_wave, expected_removed_per_wave);
+        assert_eq!(room.rolls(), stepped_room.rolls());
+    }
+
+    #[rstest]
+    #[case(Neighbourhood::Moore, 1, None)]
+    #[case(Neighbourhood::Hexagonal, 2, None)]
+    #[case(Neighbourhood::Custom, 1, Some(vec![(0, 1), (2, -1)]))]
+    fn test_dense_backend_matches_sparse(
+        #[case] neighbourhood: Neighbourhood,
+        #[case] radius: isize,
+        #[case] offsets: Option<Vec<(isize, isize)>>,
+    ) {
+        let options = RoomOptions {
+            neighbourhood,
+            radius,
+            offsets,
+            ..Default::default()
+        };
+        let mut sparse = example_room(&options, Backend::Sparse);
+        let mut dense = example_room(&options, Backend::Dense);
+
+        assert_eq!(dense.rolls(), sparse.rolls());
+        assert_eq!(dense.accessible_rolls(), sparse.accessible_rolls());
+        assert_eq!(dense.remove_until_stable(), sparse.remove_until_stable());
+        assert_eq!(dense.rolls(), sparse.rolls());
+    }
+
+    #[rstest]
+    fn test_render() {
+        let room = StorageRoom::from(EXAMPLE);
+        assert_eq!(
+            room.render(),
+            "..xx.xx@x.\nx@@.@.@.@@\n@@@@@.x.@@\n@.@@@@..@.\nx@.@@@@.@x\n.@@@@@@@.@\n.@.@.@.@@@\nx.@@@.@@@@\n.@@@@@@@@.\nx.x.@@@.x."
+        );
+    }
+
+    #[rstest]
+    fn test_render_keeps_parsed_dimensions() {
+        let mut room = StorageRoom::from("....\n.@..\n....");
+        room.remove_accessible_rolls();
+        assert_eq!(room.render(), "....\n....\n....");
+    }
+
+    #[rstest]
+    fn test_wave_map() {
+        let mut room = StorageRoom::from("@@@.\n@@@.");
+        let history = room.remove_until_stable();
+
+        assert_eq!(history.waves, 2);
+        assert_eq!(
+            history.wave_map_levels(),
+            vec![48, 128, 48, 0, 48, 128, 48, 0]
+        );
+        assert_eq!(
+            history.wave_map_colours()[..4],
+            [[127, 64, 128], [255, 64, 0], [127, 64, 128], [0, 0, 0]]
+        );
+    }
+
+    #[rstest]
+    fn test_wave_map_remaining_rolls() {
+        let mut room = StorageRoom::with_options(
+            "@...\n..@@",
+            RoomOptions {
+                threshold: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let history = room.remove_until_stable();
+
+        assert_eq!(history.remaining, HashSet::from([(1, 2), (1, 3)]));
+        assert_eq!(history.wave_map_levels(), vec![48, 0, 0, 0, 0, 0, 255, 255]);
+    }
+
+    #[rstest]
+    #[case(Backend::Sparse)]
+    #[case(Backend::Dense)]
+    fn test_add_and_remove_rolls(#[case] backend: Backend) {
+        let mut room = StorageRoom::with_options(
+            "@@.\n@@.\n...",
+            RoomOptions {
+                backend,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(room.accessible_rolls().len(), 4);
+
+        assert_eq!(room.put((0, 2)), Ok(true));
+        assert_eq!(room.put((0, 2)), Ok(false));
+        assert_eq!(room.put_all(&[(1, 2), (2, 0), (2, 1), (2, 2)]), Ok(4));
+        assert_eq!(room.neighbour_count((1, 1)), 8);
+        assert_eq!(
+            room.accessible_rolls(),
+            HashSet::from([(0, 0), (0, 2), (2, 0), (2, 2)])
+        );
+
+        assert!(room.remove_roll((1, 1)));
+        assert!(!room.remove_roll((1, 1)));
+        assert_eq!(room.neighbour_count((0, 1)), 4);
+        assert_eq!(room.rolls().len(), 8);
+    }
+
+    #[rstest]
+    #[case(Backend::Sparse)]
+    #[case(Backend::Dense)]
+    fn test_add_rolls_out_of_bounds(#[case] backend: Backend) {
+        let mut room = StorageRoom::with_options(
+            "@.\n..",
+            RoomOptions {
+                backend,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            room.put((5, -3)),
+            Err(StorageRoomError::OutOfBounds((5, -3)))
+        );
+        assert_eq!(
+            room.put_all(&[(1, 1), (2, 0)]),
+            Err(StorageRoomError::OutOfBounds((2, 0)))
+        );
+        assert_eq!(room.rolls(), HashSet::from([(0, 0)]));
+        assert_eq!(room.render(), "x.\n..");
+    }
+
+    #[rstest]
+    fn test_bounding_box_and_rect() {
+        let room = StorageRoom::from("....\n.@..\n...@\n..@.");
+        assert_eq!(room.bounding_box(), Some(((1, 1), (3, 3))));
+        assert_eq!(room.rolls_in_rect((0, 0), (2, 2)), HashSet::from([(1, 1)]));
+        assert_eq!(
+            room.rolls_in_rect((2, 2), (3, 3)),
+            HashSet::from([(2, 3), (3, 2)])
+        );
+        assert_eq!(StorageRoom::from("...").bounding_box(), None);
+    }
+
+    #[rstest]
+    fn test_connected_components() {
+        let mut room = StorageRoom::from("@@.@\n.@..\n..@.\n@...");
+        assert_eq!(
+            room.connected_components(),
+            vec![
+                HashSet::from([(0, 0), (0, 1), (1, 1), (2, 2)]),
+                HashSet::from([(0, 3)]),
+                HashSet::from([(3, 0)]),
+            ]
+        );
+
+        room.remove_roll((1, 1));
+        let labels = room.component_labels();
+        assert_eq!(labels[&(0, 0)], labels[&(0, 1)]);
+        assert_ne!(labels[&(0, 1)], labels[&(2, 2)]);
+        assert_eq!(room.connected_components().len(), 4);
+    }
+
+    #[rstest]
+    #[case(EdgeMode::Open, HashSet::from([(0, 0), (0, 2), (2, 0), (2, 2)]))]
+    #[case(EdgeMode::Torus, HashSet::new())]
+    #[case(EdgeMode::Walls, HashSet::new())]
+    fn test_edge_modes(#[case] edges: EdgeMode, #[case] expected: HashSet<(isize, isize)>) {
+        for backend in [Backend::Sparse, Backend::Dense] {
+            let room = StorageRoom::with_options(
+                "@@@\n@@@\n@@@",
+                RoomOptions {
+                    backend,
+                    edges,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(room.accessible_rolls(), expected);
+        }
+    }
+
+    #[rstest]
+    #[case(EdgeMode::Open)]
+    #[case(EdgeMode::Torus)]
+    #[case(EdgeMode::Walls)]
+    fn test_edge_modes_dense_matches_sparse(#[case] edges: EdgeMode) {
+        let options = RoomOptions {
+            neighbourhood: Neighbourhood::VonNeumann,
+            radius: 2,
+            threshold: 7,
+            edges,
+            ..Default::default()
+        };
+        let mut sparse = example_room(&options, Backend::Sparse);
+        let mut dense = example_room(&options, Backend::Dense);
+
+        assert_eq!(dense.accessible_rolls(), sparse.accessible_rolls());
+        assert_eq!(dense.remove_until_stable(), sparse.remove_until_stable());
+    }
+
+    #[rstest]
+    fn test_torus_wraps_neighbours() {
+        let room = StorageRoom::with_options(
+            "@...\n....\n...@",
+            RoomOptions {
+                edges: EdgeMode::Torus,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(room.dimensions(), (3, 4));
+        assert_eq!(room.neighbour_count((0, 0)), 1);
+        assert_eq!(room.connected_components().len(), 1);
+    }
+
+    #[rstest]
+    #[case("@@@", Neighbourhood::Moore, 1, None)]
+    #[case("@@@\n@@@", Neighbourhood::Moore, 1, None)]
+    #[case("@@@\n@@@\n@@@", Neighbourhood::VonNeumann, 2, None)]
+    #[case("@@\n@@", Neighbourhood::Custom, 1, Some(vec![(0, 1), (0, -1)]))]
+    #[case("", Neighbourhood::Moore, 1, None)]
+    fn test_torus_too_small(
+        #[case] input: &str,
+        #[case] neighbourhood: Neighbourhood,
+        #[case] radius: isize,
+        #[case] offsets: Option<Vec<(isize, isize)>>,
+    ) {
+        let options = RoomOptions {
+            neighbourhood,
+            radius,
+            offsets,
+            edges: EdgeMode::Torus,
+            ..Default::default()
+        };
+        let dimensions = parse_dimensions(input);
+        for backend in [Backend::Sparse, Backend::Dense] {
+            assert_eq!(
+                StorageRoom::with_options(
+                    input,
+                    RoomOptions {
+                        backend,
+                        ..options.clone()
+                    }
+                )
+                .err(),
+                Some(StorageRoomError::TorusTooSmall(dimensions))
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_smallest_torus() {
+        let room = StorageRoom::with_options(
+            "@@@\n@@@\n@@@",
+            RoomOptions {
+                edges: EdgeMode::Torus,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(room.neighbour_count((0, 1)), 8);
+    }
+
+    #[rstest]
+    #[case(EdgeMode::Open)]
+    #[case(EdgeMode::Torus)]
+    #[case(EdgeMode::Walls)]
+    fn test_rooms_reject_outside_rolls(#[case] edges: EdgeMode) {
+        let mut room = StorageRoom::with_options(
+            "@..\n...\n...",
+            RoomOptions {
+                edges,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(room.put((1, 1)), Ok(true));
+        assert_eq!(
+            room.put((-1, 0)),
+            Err(StorageRoomError::OutOfBounds((-1, 0)))
+        );
+    }
+
+    /// Compare the backends on a large room.
+    ///
+    /// Run with ``cargo test --release bench_backends -- --ignored --nocapture``
+    #[rstest]
+    #[ignore]
+    fn bench_backends() {
+        let size = 1000;
+        let mut state: u64 = 0x2545F4914F6CDD1D;
+        let input = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        if state % 10 < 7 {
+                            '@'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut results = Vec::new();
+        for backend in [Backend::Sparse, Backend::Dense] {
+            let start = std::time::Instant::now();
+            let mut room = StorageRoom::with_options(
+                &input,
+                RoomOptions {
+                    backend,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let built = start.elapsed();
+            let history = room.remove_until_stable();
+            let total = start.elapsed();
+            println!(
+                "{backend:?}: construct {built:?}, remove until stable {:?} ({} waves, {} removed)",
+                total - built,
+                history.waves,
+                history.removed_per_wave.iter().sum::<usize>()
+            );
+            results.push(history);
+        }
+
+        assert_eq!(results[0], results[1]);
+    }
 }
@@ -0,0 +1,227 @@
+use super::EdgeMode;
+
+/// A fixed-size grid of occupied cells stored as a bitset, along with the
+/// number of occupied neighbours of every cell.
+///
+/// Neighbour counts are kept up to date as cells are cleared, so looking up a
+/// count never has to visit the neighbours themselves.
+#[derive(PartialEq, Debug)]
+pub struct BitGrid {
+    rows: usize,
+    cols: usize,
+    /// Occupied cells, one bit per cell in row-major order
+    words: Vec<u64>,
+    /// Number of occupied neighbours of each cell, in row-major order
+    counts: Vec<u32>,
+    /// (row, col) offsets of the cells that count as neighbours
+    offsets: Vec<(isize, isize)>,
+    /// How cells beyond the edges of the grid are treated
+    edges: EdgeMode,
+}
+
+/// Row-major index of a position in a grid of the given (rows, cols), if it's
+/// within the grid
+fn index_in((rows, cols): (usize, usize), (row, col): (isize, isize)) -> Option<usize> {
+    if row < 0 || col < 0 || row as usize >= rows || col as usize >= cols {
+        None
+    } else {
+        Some(row as usize * cols + col as usize)
+    }
+}
+
+/// Row-major index of the cell a position refers to, wrapping around the
+/// edges of a toroidal grid
+fn resolve_index(
+    (rows, cols): (usize, usize),
+    edges: EdgeMode,
+    (row, col): (isize, isize),
+) -> Option<usize> {
+    match edges {
+        EdgeMode::Torus => index_in(
+            (rows, cols),
+            (row.rem_euclid(rows as isize), col.rem_euclid(cols as isize)),
+        ),
+        EdgeMode::Open | EdgeMode::Walls => index_in((rows, cols), (row, col)),
+    }
+}
+
+impl BitGrid {
+    pub fn new(
+        (rows, cols): (usize, usize),
+        offsets: &[(isize, isize)],
+        edges: EdgeMode,
+        occupied: impl IntoIterator<Item = (isize, isize)>,
+    ) -> Self {
+        let mut grid = Self {
+            rows,
+            cols,
+            words: vec![0; (rows * cols).div_ceil(64)],
+            counts: vec![0; rows * cols],
+            offsets: offsets.to_vec(),
+            edges,
+        };
+        if edges == EdgeMode::Walls {
+            // Walls never move, so they're counted once up front
+            for i in 0..rows * cols {
+                let (row, col) = grid.position(i);
+                grid.counts[i] = offsets
+                    .iter()
+                    .filter(|&&(dr, dc)| index_in((rows, cols), (row + dr, col + dc)).is_none())
+                    .count() as u32;
+            }
+        }
+        occupied.into_iter().for_each(|pos| {
+            grid.insert(pos);
+        });
+
+        grid
+    }
+
+    fn index(&self, pos: (isize, isize)) -> Option<usize> {
+        index_in((self.rows, self.cols), pos)
+    }
+
+    fn position(&self, index: usize) -> (isize, isize) {
+        ((index / self.cols) as isize, (index % self.cols) as isize)
+    }
+
+    pub fn contains(&self, pos: (isize, isize)) -> bool {
+        self.index(pos)
+            .is_some_and(|i| self.words[i / 64] & (1 << (i % 64)) != 0)
+    }
+
+    /// Number of occupied neighbours of a cell within the grid
+    pub fn neighbour_count(&self, pos: (isize, isize)) -> usize {
+        self.index(pos).map_or(0, |i| self.counts[i] as usize)
+    }
+
+    /// Adjust the counts of the in-bounds cells that count the given cell as
+    /// one of their neighbours
+    fn adjust_dependents(&mut self, (row, col): (isize, isize), occupied: bool) {
+        for &(dr, dc) in &self.offsets {
+            if let Some(d) = resolve_index((self.rows, self.cols), self.edges, (row - dr, col - dc))
+            {
+                if occupied {
+                    self.counts[d] += 1;
+                } else {
+                    self.counts[d] -= 1;
+                }
+            }
+        }
+    }
+
+    /// Mark a cell as occupied, returning whether it was previously empty
+    pub fn insert(&mut self, pos: (isize, isize)) -> bool {
+        let Some(i) = self.index(pos) else {
+            return false;
+        };
+        if self.words[i / 64] & (1 << (i % 64)) != 0 {
+            return false;
+        }
+        self.words[i / 64] |= 1 << (i % 64);
+        self.adjust_dependents(pos, true);
+
+        true
+    }
+
+    /// Mark a cell as empty, returning whether it was previously occupied
+    pub fn remove(&mut self, pos: (isize, isize)) -> bool {
+        if !self.contains(pos) {
+            return false;
+        }
+        let i = self.index(pos).unwrap();
+        self.words[i / 64] &= !(1 << (i % 64));
+        self.adjust_dependents(pos, false);
+
+        true
+    }
+
+    /// Positions of all occupied cells, in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.words.iter().enumerate().flat_map(move |(w, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                Some(self.position(w * 64 + bit))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    const MOORE: [(isize, isize); 8] = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+    ];
+
+    #[rstest]
+    fn test_neighbour_counts() {
+        let grid = BitGrid::new(
+            (3, 3),
+            &MOORE,
+            EdgeMode::Open,
+            [(0, 0), (0, 1), (1, 1), (2, 2)],
+        );
+        assert_eq!(grid.neighbour_count((1, 1)), 3);
+        assert_eq!(grid.neighbour_count((0, 0)), 2);
+        assert_eq!(grid.neighbour_count((2, 0)), 1);
+        assert_eq!(grid.neighbour_count((5, 5)), 0);
+    }
+
+    #[rstest]
+    fn test_remove_updates_counts() {
+        let mut grid = BitGrid::new(
+            (3, 3),
+            &MOORE,
+            EdgeMode::Open,
+            [(0, 0), (0, 1), (1, 1), (2, 2)],
+        );
+        assert!(grid.remove((1, 1)));
+        assert!(!grid.remove((1, 1)));
+        assert!(!grid.contains((1, 1)));
+        assert_eq!(grid.neighbour_count((0, 0)), 1);
+        assert_eq!(grid.neighbour_count((2, 2)), 0);
+        assert_eq!(grid.neighbour_count((1, 1)), 3);
+    }
+
+    #[rstest]
+    fn test_iter() {
+        let grid = BitGrid::new((9, 9), &MOORE, EdgeMode::Open, [(8, 8), (0, 3), (7, 1)]);
+        assert_eq!(
+            grid.iter().collect::<Vec<_>>(),
+            vec![(0, 3), (7, 1), (8, 8)]
+        );
+    }
+
+    #[rstest]
+    fn test_torus_neighbour_counts() {
+        let grid = BitGrid::new((3, 4), &MOORE, EdgeMode::Torus, [(0, 0), (2, 3), (1, 0)]);
+        assert_eq!(grid.neighbour_count((0, 0)), 2);
+        assert_eq!(grid.neighbour_count((2, 3)), 2);
+        assert_eq!(grid.neighbour_count((1, 3)), 3);
+    }
+
+    #[rstest]
+    fn test_walls_neighbour_counts() {
+        let mut grid = BitGrid::new((3, 3), &MOORE, EdgeMode::Walls, [(0, 0), (1, 1)]);
+        assert_eq!(grid.neighbour_count((0, 0)), 6);
+        assert_eq!(grid.neighbour_count((0, 1)), 5);
+        assert_eq!(grid.neighbour_count((1, 1)), 1);
+        grid.remove((1, 1));
+        assert_eq!(grid.neighbour_count((0, 0)), 5);
+    }
+}
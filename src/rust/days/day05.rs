@@ -1,32 +1,107 @@
-use std::collections::HashSet;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    fmt::Display,
+};
 
-use pyo3::prelude::*;
+use num_bigint::BigUint;
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyInt, PyType},
+};
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+mod range_set;
+pub use range_set::RangeSet;
 
 #[pymodule(module = "aoc_2025.rs.day05")]
 pub fn day5(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<KitchenIMS>()?;
+    m.add_class::<RangeSet>()?;
+    m.add_class::<FreshnessReport>()?;
+    m.add_class::<Change>()?;
 
     Ok(())
 }
 
+#[derive(PartialEq, Debug)]
+enum KitchenIMSError {
+    UnsortedQuery { previous: u128, next: u128 },
+    EmptyRange((u128, u128)),
+    InvalidJson(String),
+}
+
+impl Display for KitchenIMSError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsortedQuery { previous, next } => {
+                write!(
+                    f,
+                    "Query IDs must be sorted, but {next} came after {previous}"
+                )
+            }
+            Self::EmptyRange((min, max)) => write!(f, "Range {min}-{max} is empty"),
+            Self::InvalidJson(reason) => write!(f, "Invalid database JSON: {reason}"),
+        }
+    }
+}
+
+impl From<KitchenIMSError> for PyErr {
+    fn from(value: KitchenIMSError) -> Self {
+        PyValueError::new_err(value.to_string())
+    }
+}
+
 #[gen_stub_pyclass]
 #[pyclass(module = "aoc_2025.rs.day05")]
 struct KitchenIMS {
-    fresh: Vec<(usize, usize)>,
-    available: HashSet<usize>,
+    /// Fresh ranges with the line number they were listed on. Ranges added
+    /// later are numbered as if appended to the list, and keep their number
+    /// when split by a removal.
+    fresh: Vec<(usize, (u128, u128))>,
+    /// Line number given to the next fresh range added
+    next_line: usize,
+    fresh_ranges: RangeSet,
+    available: HashSet<u128>,
+    changelog: Vec<Change>,
 }
 
-impl From<&str> for KitchenIMS {
+/// Parse an inclusive range like ``3-5``. Either end can be left out to leave
+/// the range open on that side, so ``-5`` is ``0-5`` and ``3-`` runs up to the
+/// largest ID.
+fn parse_range(line: &str) -> (u128, u128) {
+    let (left, right) = line.split_once("-").unwrap();
+    (
+        if left.is_empty() {
+            0
+        } else {
+            left.parse().unwrap()
+        },
+        if right.is_empty() {
+            u128::MAX
+        } else {
+            right.parse().unwrap()
+        },
+    )
+}
+
+/// The contents of the database, as listed in the puzzle input
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Database {
+    /// Inclusive (min, max) fresh ranges
+    fresh: Vec<(u128, u128)>,
+    available: Vec<u128>,
+}
+
+impl From<&str> for Database {
     fn from(value: &str) -> Self {
         Self {
             fresh: value
                 .lines()
                 .take_while(|line| !line.is_empty())
-                .map(|line| {
-                    let (left, right) = line.split_once("-").unwrap();
-                    (left.parse().unwrap(), right.parse().unwrap())
-                })
+                .map(parse_range)
                 .collect(),
             available: value
                 .lines()
@@ -38,45 +113,185 @@ impl From<&str> for KitchenIMS {
     }
 }
 
-/// Take a set of overlapping ranges and produce an equivalent set of
-/// non-overlapping ranges
-fn consolidate_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
-    let mut old_ranges = ranges.to_owned();
-    let mut new_ranges = Vec::new();
+impl Display for Database {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Ends at the limits of the ID range are left blank, as parse_range
+        // reads a blank end
+        for &(min, max) in &self.fresh {
+            if min != 0 {
+                write!(f, "{min}")?;
+            }
+            write!(f, "-")?;
+            if max != u128::MAX {
+                write!(f, "{max}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        for id in &self.available {
+            writeln!(f, "{id}")?;
+        }
 
-    while let Some((base_min, base_max)) = old_ranges.pop() {
-        let mut overlapping_indices: Vec<usize> = Vec::new();
-        {
-            old_ranges
-                .iter()
+        Ok(())
+    }
+}
+
+impl From<Database> for KitchenIMS {
+    fn from(value: Database) -> Self {
+        Self {
+            fresh_ranges: value.fresh.iter().copied().collect(),
+            next_line: value.fresh.len() + 1,
+            fresh: value
+                .fresh
+                .into_iter()
                 .enumerate()
-                .filter_map(|(index, &(min, max))| {
-                    if (min >= base_min && min <= base_max) || (max >= base_min && max <= base_max)
-                    {
-                        Some(index)
-                    } else {
-                        None
-                    }
-                })
-                .rev()
-                .for_each(|index| overlapping_indices.push(index));
+                .map(|(index, range)| (index + 1, range))
+                .collect(),
+            available: value.available.into_iter().collect(),
+            changelog: Vec::new(),
         }
-        let mut to_combine = vec![(base_min, base_max)];
-        for index in overlapping_indices {
-            to_combine.push(old_ranges.remove(index));
+    }
+}
+
+impl From<&str> for KitchenIMS {
+    fn from(value: &str) -> Self {
+        Database::from(value).into()
+    }
+}
+
+impl KitchenIMS {
+    /// The current contents of the database, with available IDs in ascending
+    /// order. Fresh ranges are either in the order they're listed, or merged
+    /// into sorted, non-overlapping ranges if ``consolidated``.
+    fn database(&self, consolidated: bool) -> Database {
+        let mut available: Vec<u128> = self.available.iter().copied().collect();
+        available.sort_unstable();
+
+        Database {
+            fresh: if consolidated {
+                self.fresh_ranges.ranges().to_vec()
+            } else {
+                self.fresh.iter().map(|&(_, range)| range).collect()
+            },
+            available,
+        }
+    }
+
+    fn parse_json(json: &str) -> Result<Self, KitchenIMSError> {
+        let database: Database = serde_json::from_str(json)
+            .map_err(|error| KitchenIMSError::InvalidJson(error.to_string()))?;
+        if let Some(&range) = database.fresh.iter().find(|(min, max)| min > max) {
+            return Err(KitchenIMSError::EmptyRange(range));
+        }
+
+        Ok(database.into())
+    }
+
+    fn log(&mut self, operation: String, fresh_before: BigUint) {
+        self.changelog.push(Change {
+            operation,
+            fresh_before,
+            fresh_after: self.fresh_ranges.len(),
+        });
+    }
+
+    fn add_fresh(&mut self, range: (u128, u128)) -> Result<(), KitchenIMSError> {
+        if range.0 > range.1 {
+            return Err(KitchenIMSError::EmptyRange(range));
+        }
+        let before = self.fresh_ranges.len();
+
+        self.fresh.push((self.next_line, range));
+        self.next_line += 1;
+        self.fresh_ranges.insert(range);
+
+        self.log(format!("add_fresh_range({}, {})", range.0, range.1), before);
+        Ok(())
+    }
+
+    fn remove_fresh(&mut self, range: (u128, u128)) -> Result<(), KitchenIMSError> {
+        let (min, max) = range;
+        if min > max {
+            return Err(KitchenIMSError::EmptyRange(range));
         }
+        let before = self.fresh_ranges.len();
 
-        let combined_min = to_combine.iter().map(|&(min, _)| min).min().unwrap();
-        let combined_max = to_combine.iter().map(|&(_, max)| max).max().unwrap();
+        self.fresh = self
+            .fresh
+            .iter()
+            .flat_map(|&(line, (existing_min, existing_max))| {
+                if existing_max < min || existing_min > max {
+                    return vec![(line, (existing_min, existing_max))];
+                }
+                let mut pieces = Vec::new();
+                if existing_min < min {
+                    pieces.push((line, (existing_min, min - 1)));
+                }
+                if existing_max > max {
+                    pieces.push((line, (max + 1, existing_max)));
+                }
+                pieces
+            })
+            .collect();
+        self.fresh_ranges.remove(range);
 
-        new_ranges.push((combined_min, combined_max));
+        self.log(format!("remove_fresh_range({min}, {max})"), before);
+        Ok(())
     }
 
-    if new_ranges.len() != ranges.len() {
-        new_ranges = consolidate_ranges(&new_ranges);
+    fn update_available(&mut self, id: u128, available: bool) -> bool {
+        let before = self.fresh_ranges.len();
+        let changed = if available {
+            self.available.insert(id)
+        } else {
+            self.available.remove(&id)
+        };
+
+        let operation = if available { "add" } else { "remove" };
+        self.log(format!("{operation}_available({id})"), before);
+        changed
     }
 
-    new_ranges
+    /// Explain the freshness of each ID from a stream sorted in ascending
+    /// order, sweeping through the fresh ranges sorted by their start and
+    /// keeping the ranges that have started in a heap keyed by their end.
+    fn explain_sorted(
+        &self,
+        ids: impl IntoIterator<Item = u128>,
+    ) -> Result<FreshnessReport, KitchenIMSError> {
+        let mut by_start = self.fresh.clone();
+        by_start.sort_unstable_by_key(|&(_, (start, _))| start);
+        let mut by_start = by_start.into_iter().peekable();
+
+        // (end, line number) of ranges starting at or before the current ID
+        let mut started: BinaryHeap<Reverse<(u128, usize)>> = BinaryHeap::new();
+        let mut report = FreshnessReport::default();
+        let mut previous = None;
+
+        for id in ids {
+            if let Some(previous) = previous.filter(|&previous| previous > id) {
+                return Err(KitchenIMSError::UnsortedQuery { previous, next: id });
+            }
+            previous = Some(id);
+
+            while let Some((line, (_, end))) = by_start.next_if(|&(_, (start, _))| start <= id) {
+                started.push(Reverse((end, line)));
+            }
+            // IDs only increase, so a range that ends before this one is done
+            while started.peek().is_some_and(|Reverse((end, _))| *end < id) {
+                started.pop();
+            }
+
+            let mut lines: Vec<usize> = started.iter().map(|Reverse((_, line))| *line).collect();
+            lines.sort_unstable();
+            if lines.is_empty() {
+                report.spoiled.push(id);
+            }
+            report.matches.push((id, lines));
+        }
+
+        Ok(report)
+    }
 }
 
 #[gen_stub_pymethods]
@@ -87,19 +302,168 @@ impl KitchenIMS {
         Self::from(value)
     }
 
-    fn available_and_fresh(&self) -> HashSet<usize> {
+    fn available_and_fresh(&self) -> HashSet<u128> {
         self.available
             .iter()
-            .filter(|&&id| self.fresh.iter().any(|&(min, max)| id >= min && id <= max))
+            .filter(|&&id| self.fresh_ranges.contains(id))
             .cloned()
             .collect()
     }
 
-    fn fresh_ids_count(&self) -> usize {
-        consolidate_ranges(&self.fresh)
-            .iter()
-            .map(|(min, max)| max + 1 - min)
-            .sum()
+    fn fresh_ids_count<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.fresh_ranges.len().into_pyobject(py)
+    }
+
+    /// For each ID, list the line numbers of the fresh ranges that contain it.
+    ///
+    /// ``ids`` must be in ascending order, and defaults to all available IDs.
+    #[pyo3(signature = (ids=None))]
+    fn explain(&self, ids: Option<Vec<u128>>) -> PyResult<FreshnessReport> {
+        Ok(match ids {
+            Some(ids) => self.explain_sorted(ids)?,
+            None => {
+                let mut available: Vec<u128> = self.available.iter().copied().collect();
+                available.sort_unstable();
+                self.explain_sorted(available)?
+            }
+        })
+    }
+
+    /// Fresh IDs in either database
+    fn fresh_union(&self, other: &KitchenIMS) -> RangeSet {
+        self.fresh_ranges.union(&other.fresh_ranges)
+    }
+
+    /// Fresh IDs in both databases
+    fn fresh_intersection(&self, other: &KitchenIMS) -> RangeSet {
+        self.fresh_ranges.intersection(&other.fresh_ranges)
+    }
+
+    /// Fresh IDs in this database but not the other
+    fn fresh_difference(&self, other: &KitchenIMS) -> RangeSet {
+        self.fresh_ranges.difference(&other.fresh_ranges)
+    }
+
+    /// Fresh IDs in exactly one of the databases
+    fn fresh_symmetric_difference(&self, other: &KitchenIMS) -> RangeSet {
+        self.fresh_ranges.symmetric_difference(&other.fresh_ranges)
+    }
+
+    /// IDs from ``min`` to ``max`` inclusive that aren't fresh
+    fn fresh_complement(&self, min: u128, max: u128) -> RangeSet {
+        self.fresh_ranges.complement(min, max)
+    }
+
+    /// The fresh ranges merged into sorted, non-overlapping ranges
+    #[getter]
+    fn fresh_ranges(&self) -> RangeSet {
+        self.fresh_ranges.clone()
+    }
+
+    /// Mark the inclusive range ``min`` to ``max`` as fresh
+    fn add_fresh_range(&mut self, min: u128, max: u128) -> PyResult<()> {
+        Ok(self.add_fresh((min, max))?)
+    }
+
+    /// Mark the inclusive range ``min`` to ``max`` as no longer fresh,
+    /// splitting any fresh range that only partly overlaps it
+    fn remove_fresh_range(&mut self, min: u128, max: u128) -> PyResult<()> {
+        Ok(self.remove_fresh((min, max))?)
+    }
+
+    /// Add an available ID, returning whether it wasn't already available
+    fn add_available(&mut self, id: u128) -> bool {
+        self.update_available(id, true)
+    }
+
+    /// Remove an available ID, returning whether it was available
+    fn remove_available(&mut self, id: u128) -> bool {
+        self.update_available(id, false)
+    }
+
+    /// The database in the puzzle input format. See ``to_json`` for the order
+    /// of ranges and IDs.
+    #[pyo3(signature = (consolidated=None))]
+    fn to_text(&self, consolidated: Option<bool>) -> String {
+        self.database(consolidated.unwrap_or(false)).to_string()
+    }
+
+    /// The database as a JSON object with a ``fresh`` list of ``[min, max]``
+    /// ranges and an ``available`` list of IDs in ascending order. Fresh
+    /// ranges are in the order they're listed, or merged into sorted,
+    /// non-overlapping ranges if ``consolidated``.
+    #[pyo3(signature = (consolidated=None))]
+    fn to_json(&self, consolidated: Option<bool>) -> String {
+        serde_json::to_string(&self.database(consolidated.unwrap_or(false))).unwrap()
+    }
+
+    /// Load a database exported by ``to_json``
+    #[classmethod]
+    #[pyo3(signature = (json))]
+    fn from_json(_cls: &Bound<'_, PyType>, json: &str) -> PyResult<Self> {
+        Ok(Self::parse_json(json)?)
+    }
+
+    /// Every update made since the database was loaded, oldest first
+    #[getter]
+    fn changelog(&self) -> Vec<Change> {
+        self.changelog.clone()
+    }
+}
+
+/// An update to the database and its effect on the number of fresh IDs
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day05")]
+#[derive(PartialEq, Clone, Debug)]
+struct Change {
+    #[pyo3(get)]
+    /// The update made, like ``add_fresh_range(3, 5)``
+    operation: String,
+
+    fresh_before: BigUint,
+    fresh_after: BigUint,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Change {
+    /// Number of fresh IDs before the update
+    #[getter]
+    fn fresh_before<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.fresh_before.clone().into_pyobject(py)
+    }
+
+    /// Number of fresh IDs after the update
+    #[getter]
+    fn fresh_after<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.fresh_after.clone().into_pyobject(py)
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day05")]
+#[derive(PartialEq, Debug, Default)]
+struct FreshnessReport {
+    #[pyo3(get)]
+    /// Each queried ID with the line numbers of the fresh ranges containing it
+    matches: Vec<(u128, Vec<usize>)>,
+
+    #[pyo3(get)]
+    /// Queried IDs not in any fresh range, in ascending order
+    spoiled: Vec<u128>,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl FreshnessReport {
+    /// Number of queried IDs that are fresh
+    fn fresh_count(&self) -> usize {
+        self.matches.len() - self.spoiled.len()
+    }
+
+    /// Number of queried IDs that are spoiled
+    fn spoiled_count(&self) -> usize {
+        self.spoiled.len()
     }
 }
 
@@ -112,7 +476,8 @@ mod tests {
     fn test_create_kitchen_ims() {
         let example_input = "3-5\n10-14\n\n1\n5";
         let ims = KitchenIMS::from(example_input);
-        assert_eq!(ims.fresh, vec![(3, 5), (10, 14)]);
+        assert_eq!(ims.fresh, vec![(1, (3, 5)), (2, (10, 14))]);
+        assert_eq!(ims.fresh_ranges.ranges(), vec![(3, 5), (10, 14)]);
         assert_eq!(ims.available, HashSet::from([1, 5]));
     }
 
@@ -124,22 +489,200 @@ mod tests {
     }
 
     #[rstest]
-    fn test_consolidate_ranges() {
-        let ranges = vec![
-            (1, 5),
-            (4, 10),
-            (8, 15),
-            (20, 30),
-            (30, 40),
-            (50, 60),
-            (102, 105),
-            (101, 107),
-            (105, 106),
+    fn test_fresh_ranges() {
+        let example_input = "1-5\n4-10\n8-15\n20-30\n30-40\n50-60\n102-105\n101-107\n105-106\n\n1";
+        let ims = KitchenIMS::from(example_input);
+
+        assert_eq!(
+            ims.fresh_ranges.ranges(),
+            vec![(1, 15), (20, 40), (50, 60), (101, 107)]
+        );
+        assert_eq!(ims.fresh_ranges.len(), BigUint::from(15u32 + 21 + 11 + 7));
+    }
+
+    #[rstest]
+    fn test_compare_databases() {
+        let ours = KitchenIMS::from("3-5\n10-14\n16-20\n12-18\n\n1");
+        let theirs = KitchenIMS::from("1-4\n15-30\n\n1");
+
+        let union = ours.fresh_union(&theirs);
+        assert_eq!(union.ranges(), [(1, 5), (10, 30)]);
+        assert_eq!(union.len(), BigUint::from(26u32));
+        assert_eq!(
+            ours.fresh_intersection(&theirs).ranges(),
+            [(3, 4), (15, 20)]
+        );
+        assert_eq!(ours.fresh_difference(&theirs).ranges(), [(5, 5), (10, 14)]);
+        assert_eq!(
+            ours.fresh_symmetric_difference(&theirs).ranges(),
+            [(1, 2), (5, 5), (10, 14), (21, 30)]
+        );
+        assert_eq!(
+            ours.fresh_complement(0, 25).ranges(),
+            [(0, 2), (6, 9), (21, 25)]
+        );
+    }
+
+    #[rstest]
+    fn test_explain_sorted() {
+        let ims = KitchenIMS::from("3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32");
+        let report = ims.explain_sorted([1, 5, 8, 11, 12, 17, 17, 32]).unwrap();
+
+        assert_eq!(
+            report.matches,
+            vec![
+                (1, vec![]),
+                (5, vec![1]),
+                (8, vec![]),
+                (11, vec![2]),
+                (12, vec![2, 4]),
+                (17, vec![3, 4]),
+                (17, vec![3, 4]),
+                (32, vec![]),
+            ]
+        );
+        assert_eq!(report.spoiled, vec![1, 8, 32]);
+        assert_eq!(report.fresh_count(), 5);
+    }
+
+    #[rstest]
+    fn test_explain_unsorted() {
+        let ims = KitchenIMS::from("3-5\n10-14\n\n1");
+        assert_eq!(
+            ims.explain_sorted([4, 12, 11]),
+            Err(KitchenIMSError::UnsortedQuery {
+                previous: 12,
+                next: 11
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("3-5", (3, 5))]
+    #[case("100-", (100, u128::MAX))]
+    #[case("-50", (0, 50))]
+    #[case("-", (0, u128::MAX))]
+    #[case(
+        "0-340282366920938463463374607431768211455",
+        (0, u128::MAX)
+    )]
+    fn test_parse_range(#[case] line: &str, #[case] expected: (u128, u128)) {
+        assert_eq!(parse_range(line), expected);
+    }
+
+    #[rstest]
+    fn test_count_beyond_u64() {
+        let ims = KitchenIMS::from("0-18446744073709551615\n10-\n\n1");
+        assert_eq!(ims.fresh_ranges.ranges(), [(0, u128::MAX)]);
+        assert_eq!(ims.fresh_ranges.len(), BigUint::from(u128::MAX) + 1u32);
+    }
+
+    #[rstest]
+    fn test_incremental_updates() {
+        let mut ims = KitchenIMS::from("3-5\n10-14\n\n1\n5");
+
+        ims.add_fresh((6, 8)).unwrap();
+        assert_eq!(ims.fresh_ranges.ranges(), [(3, 8), (10, 14)]);
+        ims.remove_fresh((4, 11)).unwrap();
+        assert_eq!(ims.fresh_ranges.ranges(), [(3, 3), (12, 14)]);
+        assert_eq!(ims.fresh, vec![(1, (3, 3)), (2, (12, 14))]);
+        assert!(ims.update_available(12, true));
+        assert!(!ims.update_available(7, false));
+        assert_eq!(
+            ims.add_fresh((9, 8)),
+            Err(KitchenIMSError::EmptyRange((9, 8)))
+        );
+
+        let counts: Vec<(&str, u32, u32)> = vec![
+            ("add_fresh_range(6, 8)", 8, 11),
+            ("remove_fresh_range(4, 11)", 11, 4),
+            ("add_available(12)", 4, 4),
+            ("remove_available(7)", 4, 4),
         ];
+        assert_eq!(
+            ims.changelog,
+            counts
+                .into_iter()
+                .map(|(operation, before, after)| Change {
+                    operation: operation.to_string(),
+                    fresh_before: before.into(),
+                    fresh_after: after.into(),
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(ims.available_and_fresh(), HashSet::from([12]));
+    }
 
+    #[rstest]
+    fn test_explain_after_updates() {
+        let mut ims = KitchenIMS::from("3-5\n10-14\n\n1");
+        ims.add_fresh((1, 20)).unwrap();
+        ims.remove_fresh((4, 11)).unwrap();
+
+        let report = ims.explain_sorted([3, 4, 12]).unwrap();
         assert_eq!(
-            consolidate_ranges(&ranges),
-            vec![(101, 107), (50, 60), (20, 40), (1, 15)]
+            report.matches,
+            vec![(3, vec![1, 3]), (4, vec![]), (12, vec![2, 3])]
         );
     }
+
+    const EXAMPLE: &str = include_str!("../../../data/test/d5");
+
+    #[rstest]
+    #[case(EXAMPLE)]
+    #[case("100-\n-50\n-\n3-5\n\n1\n")]
+    fn test_text_round_trip(#[case] input: &str) {
+        assert_eq!(KitchenIMS::from(input).database(false).to_string(), input);
+    }
+
+    #[rstest]
+    fn test_consolidated_text() {
+        assert_eq!(
+            KitchenIMS::from(EXAMPLE).database(true).to_string(),
+            "3-5\n10-20\n\n1\n5\n8\n11\n17\n32\n"
+        );
+    }
+
+    #[rstest]
+    fn test_json_round_trip() {
+        let ims = KitchenIMS::from(EXAMPLE);
+        let json = serde_json::to_string(&ims.database(false)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"fresh":[[3,5],[10,14],[16,20],[12,18]],"available":[1,5,8,11,17,32]}"#
+        );
+
+        let loaded = KitchenIMS::parse_json(&json).unwrap();
+        assert_eq!(loaded.database(false).to_string(), EXAMPLE);
+        assert_eq!(loaded.fresh, ims.fresh);
+        assert_eq!(loaded.fresh_ranges, ims.fresh_ranges);
+    }
+
+    #[rstest]
+    fn test_json_beyond_u64() {
+        let json = r#"{"fresh":[[0,340282366920938463463374607431768211455]],"available":[]}"#;
+        let loaded = KitchenIMS::parse_json(json).unwrap();
+        assert_eq!(loaded.fresh_ranges.ranges(), [(0, u128::MAX)]);
+        assert_eq!(
+            serde_json::to_string(&loaded.database(false)).unwrap(),
+            json
+        );
+    }
+
+    #[rstest]
+    #[case(r#"{"fresh":[[5,3]],"available":[]}"#, KitchenIMSError::EmptyRange((5, 3)))]
+    fn test_invalid_json(#[case] json: &str, #[case] expected: KitchenIMSError) {
+        assert_eq!(KitchenIMS::parse_json(json).err(), Some(expected));
+    }
+
+    #[rstest]
+    #[case(r#"{"fresh":[[1,2]]}"#)]
+    #[case(r#"{"fresh":[[-1,2]],"available":[]}"#)]
+    #[case("[]")]
+    fn test_malformed_json(#[case] json: &str) {
+        assert!(matches!(
+            KitchenIMS::parse_json(json),
+            Err(KitchenIMSError::InvalidJson(_))
+        ));
+    }
 }
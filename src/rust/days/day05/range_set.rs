@@ -0,0 +1,432 @@
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyOverflowError, prelude::*, types::PyInt};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+/// A set of integers stored as sorted, disjoint, inclusive ranges.
+///
+/// Ranges are merged as they're inserted, including ranges that are only
+/// adjacent to each other, so each covered integer is in exactly one range.
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day05")]
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RangeSet {
+    ranges: Vec<(u128, u128)>,
+    /// Number of integers covered, kept up to date as ranges change
+    count: BigUint,
+}
+
+/// Number of integers in an inclusive range, which can be one more than fits
+/// in a u128
+fn width((min, max): (u128, u128)) -> BigUint {
+    BigUint::from(max - min) + 1u32
+}
+
+impl FromIterator<(u128, u128)> for RangeSet {
+    fn from_iter<T: IntoIterator<Item = (u128, u128)>>(iter: T) -> Self {
+        let mut set = Self::default();
+        iter.into_iter().for_each(|range| set.insert(range));
+        set
+    }
+}
+
+impl RangeSet {
+    /// The (min, max) ranges of the set in ascending order
+    pub fn ranges(&self) -> &[(u128, u128)] {
+        &self.ranges
+    }
+
+    /// Build a set from ranges that are already sorted, disjoint and not
+    /// adjacent to each other
+    fn from_disjoint(ranges: Vec<(u128, u128)>) -> Self {
+        Self {
+            count: ranges.iter().copied().map(width).sum(),
+            ranges,
+        }
+    }
+
+    /// Number of integers covered by the ranges
+    pub fn len(&self) -> BigUint {
+        self.count.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl RangeSet {
+    #[new]
+    #[pyo3(signature = (ranges=None))]
+    fn new(ranges: Option<Vec<(u128, u128)>>) -> Self {
+        ranges.unwrap_or_default().into_iter().collect()
+    }
+
+    /// Add the inclusive range ``min`` to ``max``, merging it with any ranges
+    /// it overlaps or touches. Empty ranges, where ``min > max``, are ignored.
+    pub fn insert(&mut self, range: (u128, u128)) {
+        let (min, max) = range;
+        if min > max {
+            return;
+        }
+
+        // Ranges from `first` up to (but not including) `last` overlap or are
+        // adjacent to the new range
+        let first = self
+            .ranges
+            .partition_point(|&(_, existing_max)| existing_max.saturating_add(1) < min);
+        let last = self
+            .ranges
+            .partition_point(|&(existing_min, _)| existing_min <= max.saturating_add(1));
+
+        let merged = if first < last {
+            (
+                min.min(self.ranges[first].0),
+                max.max(self.ranges[last - 1].1),
+            )
+        } else {
+            (min, max)
+        };
+        self.count += width(merged);
+        for replaced in self.ranges.splice(first..last, [merged]) {
+            self.count -= width(replaced);
+        }
+    }
+
+    /// Remove the inclusive range ``min`` to ``max``, splitting any range that
+    /// only partly overlaps it. Empty ranges, where ``min > max``, are ignored.
+    pub fn remove(&mut self, range: (u128, u128)) {
+        let (min, max) = range;
+        if min > max {
+            return;
+        }
+
+        // Ranges from `first` up to (but not including) `last` overlap the
+        // removed range
+        let first = self
+            .ranges
+            .partition_point(|&(_, existing_max)| existing_max < min);
+        let last = self
+            .ranges
+            .partition_point(|&(existing_min, _)| existing_min <= max);
+        if first >= last {
+            return;
+        }
+
+        let mut kept = Vec::new();
+        if self.ranges[first].0 < min {
+            kept.push((self.ranges[first].0, min - 1));
+        }
+        if self.ranges[last - 1].1 > max {
+            kept.push((max + 1, self.ranges[last - 1].1));
+        }
+
+        self.count += kept.iter().copied().map(width).sum::<BigUint>();
+        for removed in self.ranges.splice(first..last, kept) {
+            self.count -= width(removed);
+        }
+    }
+
+    /// Whether the value is in any of the ranges
+    pub fn contains(&self, value: u128) -> bool {
+        let index = self
+            .ranges
+            .partition_point(|&(_, existing_max)| existing_max < value);
+        self.ranges
+            .get(index)
+            .is_some_and(|&(existing_min, _)| existing_min <= value)
+    }
+
+    /// Integers in either set
+    pub fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = [self.ranges.as_slice(), other.ranges.as_slice()].concat();
+        // Sorted input means each insert only ever touches the last range
+        ranges.sort_unstable();
+        ranges.into_iter().collect()
+    }
+
+    /// Integers in both sets
+    pub fn intersection(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(&(a_min, a_max)), Some(&(b_min, b_max))) =
+            (self.ranges.get(i), other.ranges.get(j))
+        {
+            let (min, max) = (a_min.max(b_min), a_max.min(b_max));
+            if min <= max {
+                ranges.push((min, max));
+            }
+            // Whichever range ends first can't overlap anything further on
+            if a_max < b_max {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        RangeSet::from_disjoint(ranges)
+    }
+
+    /// Integers in this set but not the other
+    pub fn difference(&self, other: &RangeSet) -> RangeSet {
+        self.intersection(&other.complement(0, u128::MAX))
+    }
+
+    /// Integers in exactly one of the sets
+    pub fn symmetric_difference(&self, other: &RangeSet) -> RangeSet {
+        self.difference(other).union(&other.difference(self))
+    }
+
+    /// Integers from ``min`` to ``max`` inclusive that aren't in the set
+    pub fn complement(&self, min: u128, max: u128) -> RangeSet {
+        if min > max {
+            return RangeSet::default();
+        }
+
+        let mut ranges = Vec::new();
+        // First integer not yet known to be covered or output
+        let mut next = min;
+        for &(existing_min, existing_max) in &self.ranges {
+            if existing_max < next {
+                continue;
+            }
+            if existing_min > max {
+                break;
+            }
+            if existing_min > next {
+                ranges.push((next, existing_min - 1));
+            }
+            match existing_max.checked_add(1) {
+                Some(after) if after <= max => next = after,
+                _ => return RangeSet::from_disjoint(ranges),
+            }
+        }
+        ranges.push((next, max));
+
+        RangeSet::from_disjoint(ranges)
+    }
+
+    fn __or__(&self, other: &RangeSet) -> RangeSet {
+        self.union(other)
+    }
+
+    fn __and__(&self, other: &RangeSet) -> RangeSet {
+        self.intersection(other)
+    }
+
+    fn __sub__(&self, other: &RangeSet) -> RangeSet {
+        self.difference(other)
+    }
+
+    fn __xor__(&self, other: &RangeSet) -> RangeSet {
+        self.symmetric_difference(other)
+    }
+
+    fn __eq__(&self, other: &RangeSet) -> bool {
+        self == other
+    }
+
+    fn __contains__(&self, value: u128) -> bool {
+        self.contains(value)
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        usize::try_from(self.len())
+            .map_err(|_| PyOverflowError::new_err("Use count() for sets this large"))
+    }
+
+    /// Number of integers covered by the ranges
+    fn count<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.len().into_pyobject(py)
+    }
+
+    /// Iterate over the (min, max) ranges in ascending order
+    fn __iter__(&self) -> RangeSetIterator {
+        RangeSetIterator {
+            ranges: self.ranges.clone().into_iter(),
+        }
+    }
+
+    /// The (min, max) ranges in ascending order
+    #[getter(ranges)]
+    fn get_ranges(&self) -> Vec<(u128, u128)> {
+        self.ranges.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RangeSet({:?})", self.ranges)
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day05")]
+pub struct RangeSetIterator {
+    ranges: std::vec::IntoIter<(u128, u128)>,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl RangeSetIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<(u128, u128)> {
+        self.ranges.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(vec![(3, 5), (10, 14), (16, 20), (12, 18)], vec![(3, 5), (10, 20)])]
+    #[case(vec![(1, 5), (6, 9)], vec![(1, 9)])]
+    #[case(vec![(6, 9), (1, 5)], vec![(1, 9)])]
+    #[case(vec![(1, 4), (6, 9)], vec![(1, 4), (6, 9)])]
+    #[case(vec![(1, 2), (5, 6), (9, 10), (3, 8)], vec![(1, 10)])]
+    #[case(vec![(5, 6), (0, 0), (100, 200), (7, 7)], vec![(0, 0), (5, 7), (100, 200)])]
+    #[case(vec![(5, 4)], vec![])]
+    #[case(vec![(0, u128::MAX), (3, 4)], vec![(0, u128::MAX)])]
+    fn test_insert(#[case] ranges: Vec<(u128, u128)>, #[case] expected: Vec<(u128, u128)>) {
+        let set: RangeSet = ranges.into_iter().collect();
+        assert_eq!(set.ranges(), expected);
+    }
+
+    #[rstest]
+    #[case((0, 2), &[(3, 5), (10, 20)], 14)]
+    #[case((3, 5), &[(10, 20)], 11)]
+    #[case((4, 4), &[(3, 3), (5, 5), (10, 20)], 13)]
+    #[case((5, 10), &[(3, 4), (11, 20)], 12)]
+    #[case((0, 100), &[], 0)]
+    #[case((15, 14), &[(3, 5), (10, 20)], 14)]
+    fn test_remove(
+        #[case] range: (u128, u128),
+        #[case] expected: &[(u128, u128)],
+        #[case] expected_len: u32,
+    ) {
+        let mut set = set(&[(3, 5), (10, 20)]);
+        set.remove(range);
+        assert_eq!(set.ranges(), expected);
+        assert_eq!(set.len(), BigUint::from(expected_len));
+    }
+
+    #[rstest]
+    fn test_remove_from_everything() {
+        let mut set = set(&[(0, u128::MAX)]);
+        set.remove((0, 0));
+        set.remove((u128::MAX, u128::MAX));
+        assert_eq!(set.ranges(), [(1, u128::MAX - 1)]);
+        assert_eq!(set.len(), BigUint::from(u128::MAX) - 1u32);
+    }
+
+    #[rstest]
+    #[case(0, false)]
+    #[case(3, true)]
+    #[case(5, true)]
+    #[case(6, false)]
+    #[case(10, true)]
+    #[case(20, true)]
+    #[case(21, false)]
+    fn test_contains(#[case] value: u128, #[case] expected: bool) {
+        let set: RangeSet = [(3, 5), (10, 14), (16, 20), (12, 18)].into_iter().collect();
+        assert_eq!(set.contains(value), expected);
+    }
+
+    fn set(ranges: &[(u128, u128)]) -> RangeSet {
+        ranges.iter().copied().collect()
+    }
+
+    #[rstest]
+    #[case(&[(1, 5), (10, 20)], &[(6, 8), (15, 30)], &[(1, 8), (10, 30)])]
+    #[case(&[(1, 5)], &[(7, 9)], &[(1, 5), (7, 9)])]
+    #[case(&[], &[(7, 9)], &[(7, 9)])]
+    fn test_union(
+        #[case] a: &[(u128, u128)],
+        #[case] b: &[(u128, u128)],
+        #[case] expected: &[(u128, u128)],
+    ) {
+        assert_eq!(set(a).union(&set(b)).ranges(), expected);
+        assert_eq!(set(b).union(&set(a)).ranges(), expected);
+    }
+
+    #[rstest]
+    #[case(&[(1, 5), (10, 20)], &[(4, 12), (15, 16), (18, 30)], &[(4, 5), (10, 12), (15, 16), (18, 20)])]
+    #[case(&[(1, 5)], &[(6, 9)], &[])]
+    #[case(&[(0, 100)], &[(3, 3), (50, 60)], &[(3, 3), (50, 60)])]
+    fn test_intersection(
+        #[case] a: &[(u128, u128)],
+        #[case] b: &[(u128, u128)],
+        #[case] expected: &[(u128, u128)],
+    ) {
+        assert_eq!(set(a).intersection(&set(b)).ranges(), expected);
+        assert_eq!(set(b).intersection(&set(a)).ranges(), expected);
+    }
+
+    #[rstest]
+    #[case(&[(1, 20)], &[(4, 6), (10, 12)], &[(1, 3), (7, 9), (13, 20)])]
+    #[case(&[(1, 5), (10, 20)], &[(0, 30)], &[])]
+    #[case(&[(1, 5), (10, 20)], &[(5, 10)], &[(1, 4), (11, 20)])]
+    #[case(&[(0, u128::MAX)], &[(0, 0)], &[(1, u128::MAX)])]
+    fn test_difference(
+        #[case] a: &[(u128, u128)],
+        #[case] b: &[(u128, u128)],
+        #[case] expected: &[(u128, u128)],
+    ) {
+        assert_eq!(set(a).difference(&set(b)).ranges(), expected);
+    }
+
+    #[rstest]
+    #[case(&[(1, 10)], &[(5, 15)], &[(1, 4), (11, 15)])]
+    #[case(&[(1, 10)], &[(1, 10)], &[])]
+    #[case(&[(1, 4)], &[(5, 8)], &[(1, 8)])]
+    fn test_symmetric_difference(
+        #[case] a: &[(u128, u128)],
+        #[case] b: &[(u128, u128)],
+        #[case] expected: &[(u128, u128)],
+    ) {
+        assert_eq!(set(a).symmetric_difference(&set(b)).ranges(), expected);
+        assert_eq!(set(b).symmetric_difference(&set(a)).ranges(), expected);
+    }
+
+    #[rstest]
+    #[case(0, 30, &[(0, 2), (6, 9), (21, 30)])]
+    #[case(4, 12, &[(6, 9)])]
+    #[case(3, 5, &[])]
+    #[case(7, 8, &[(7, 8)])]
+    #[case(8, 7, &[])]
+    #[case(15, u128::MAX, &[(21, u128::MAX)])]
+    fn test_complement(#[case] min: u128, #[case] max: u128, #[case] expected: &[(u128, u128)]) {
+        assert_eq!(
+            set(&[(3, 5), (10, 20)]).complement(min, max).ranges(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_complement_of_everything() {
+        assert!(set(&[(0, u128::MAX)]).complement(0, u128::MAX).is_empty());
+        assert_eq!(
+            RangeSet::default().complement(0, u128::MAX).ranges(),
+            [(0, u128::MAX)]
+        );
+    }
+
+    #[rstest]
+    fn test_len_of_everything() {
+        assert_eq!(
+            set(&[(0, 10), (5, u128::MAX)]).len(),
+            BigUint::from(u128::MAX) + 1u32
+        );
+    }
+
+    #[rstest]
+    fn test_len() {
+        let set: RangeSet = [(3, 5), (10, 14), (16, 20), (12, 18)].into_iter().collect();
+        assert_eq!(set.len(), BigUint::from(14u32));
+        assert!(RangeSet::default().is_empty());
+    }
+}
@@ -1,36 +1,517 @@
-use core::panic;
+use std::fmt::Display;
 
-use pyo3::{prelude::*, types::PyType};
-use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, ToPrimitive, Zero};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyInt, PyType},
+};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
 
 #[pymodule(module = "aoc_2026.rs.day06")]
 pub fn day6(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Homework>()?;
+    m.add_class::<Problem>()?;
+    m.add_class::<Operation>()?;
+    m.add_class::<Layout>()?;
 
     Ok(())
 }
 
 #[derive(PartialEq, Debug)]
+enum HomeworkError {
+    NoValues,
+    InvalidValue(String),
+    UnknownOperator(String),
+    /// A problem needs either one operator, or one between each pair of values
+    MismatchedOperators {
+        values: usize,
+        operators: usize,
+    },
+    DivisionByZero,
+    InexactDivision(String, String),
+    NegativeExponent(String),
+    /// The result doesn't fit in the integer type the problem was solved with,
+    /// or is a power too large to compute
+    Overflow,
+    /// The worksheet reads validly but differently in more than one layout
+    AmbiguousLayout(Vec<Layout>),
+    UnrecognisedLayout,
+    /// A line of values doesn't have one value for each problem
+    MisalignedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The operator line doesn't have one operator for each problem
+    OperatorCount {
+        problems: usize,
+        operators: usize,
+    },
+    /// A problem's operator isn't written under the columns of its values
+    MisplacedOperator((usize, usize)),
+    /// A line has more than one token in the columns of the same problem
+    CrowdedLine {
+        line: usize,
+        span: (usize, usize),
+    },
+    /// Worksheets can't be written without picking a particular layout
+    UnrenderableLayout(Layout),
+    /// The problem at the given index can't be written in the layout, or
+    /// doesn't have as many values as the problems before it
+    UnrenderableProblem {
+        layout: Layout,
+        problem: usize,
+    },
+}
+
+impl Display for HomeworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoValues => write!(f, "No problem values"),
+            Self::InvalidValue(value) => write!(f, "Invalid value {value:?}"),
+            Self::UnknownOperator(operator) => write!(f, "Unknown operator {operator:?}"),
+            Self::MismatchedOperators { values, operators } => write!(
+                f,
+                "Can't combine {values} values with {operators} operators"
+            ),
+            Self::DivisionByZero => write!(f, "Division by zero"),
+            Self::InexactDivision(left, right) => {
+                write!(f, "{left} isn't divisible by {right}")
+            }
+            Self::NegativeExponent(exponent) => write!(f, "Negative exponent {exponent}"),
+            Self::Overflow => write!(f, "Arithmetic overflow"),
+            Self::AmbiguousLayout(layouts) => {
+                write!(
+                    f,
+                    "Worksheet could be read in any of the layouts {layouts:?}"
+                )
+            }
+            Self::UnrecognisedLayout => write!(f, "Worksheet doesn't match any layout"),
+            Self::MisalignedLine {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {line} has {found} values, but there are {expected} problems"
+            ),
+            Self::OperatorCount {
+                problems,
+                operators,
+            } => write!(f, "Found {operators} operators for {problems} problems"),
+            Self::MisplacedOperator((start, end)) => write!(
+                f,
+                "No operator under the problem in columns {start} to {end}"
+            ),
+            Self::CrowdedLine {
+                line,
+                span: (start, end),
+            } => write!(
+                f,
+                "Line {line} has more than one token in the problem in columns {start} to {end}"
+            ),
+            Self::UnrenderableLayout(layout) => {
+                write!(f, "Can't write a worksheet in the {layout:?} layout")
+            }
+            Self::UnrenderableProblem { layout, problem } => {
+                write!(f, "Can't write problem {problem} in the {layout:?} layout")
+            }
+        }
+    }
+}
+
+impl From<HomeworkError> for PyErr {
+    fn from(value: HomeworkError) -> Self {
+        PyValueError::new_err(value.to_string())
+    }
+}
+
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day06")]
+#[derive(PartialEq, Clone, Copy, Debug)]
 enum Operation {
     Add,
+    Sub,
     Mult,
+    /// Division that must leave no remainder
+    Div,
+    Rem,
+    Pow,
+    Min,
+    Max,
 }
 
-#[derive(PartialEq, Debug)]
+impl TryFrom<&str> for Operation {
+    type Error = HomeworkError;
+
+    /// Min and max can be written as ``<`` and ``>`` where operators have to
+    /// be a single character
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "+" => Ok(Self::Add),
+            "-" => Ok(Self::Sub),
+            "*" => Ok(Self::Mult),
+            "/" => Ok(Self::Div),
+            "%" => Ok(Self::Rem),
+            "^" => Ok(Self::Pow),
+            "<" | "min" => Ok(Self::Min),
+            ">" | "max" => Ok(Self::Max),
+            _ => Err(HomeworkError::UnknownOperator(value.to_owned())),
+        }
+    }
+}
+
+/// Integer types problems can be solved with, where any operation that
+/// doesn't fit in the type gives ``None``
+trait Integer:
+    Clone
+    + Ord
+    + Display
+    + From<isize>
+    + Zero
+    + One
+    + ToPrimitive
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + CheckedDiv
+{
+}
+
+impl<T> Integer for T where
+    T: Clone
+        + Ord
+        + Display
+        + From<isize>
+        + Zero
+        + One
+        + ToPrimitive
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+{
+}
+
+/// Most bits a power can have before it's treated as overflowing, even with
+/// arbitrary precision
+const MAX_POW_BITS: f64 = 65_536.0;
+
+/// Quotient and remainder of truncating division by a non-zero divisor
+fn checked_div_rem<N: Integer>(left: &N, right: &N) -> Option<(N, N)> {
+    let quotient = left.checked_div(right)?;
+    let remainder = left.checked_sub(&quotient.checked_mul(right)?)?;
+    Some((quotient, remainder))
+}
+
+impl Operation {
+    fn apply<N: Integer>(self, left: N, right: N) -> Result<N, HomeworkError> {
+        let result = match self {
+            Self::Add => left.checked_add(&right),
+            Self::Sub => left.checked_sub(&right),
+            Self::Mult => left.checked_mul(&right),
+            Self::Div | Self::Rem if right.is_zero() => return Err(HomeworkError::DivisionByZero),
+            Self::Div => match checked_div_rem(&left, &right) {
+                Some((_, remainder)) if !remainder.is_zero() => {
+                    return Err(HomeworkError::InexactDivision(
+                        left.to_string(),
+                        right.to_string(),
+                    ))
+                }
+                result => result.map(|(quotient, _)| quotient),
+            },
+            Self::Rem => checked_div_rem(&left, &right).map(|(_, remainder)| remainder),
+            Self::Pow => {
+                if right < N::zero() {
+                    return Err(HomeworkError::NegativeExponent(right.to_string()));
+                }
+                // Checked before sizing the result, which is NaN for huge bases
+                if right.is_zero() {
+                    return Ok(N::one());
+                }
+                let base_bits = left
+                    .to_f64()
+                    .map_or(f64::INFINITY, |base| base.abs().log2());
+                right
+                    .to_usize()
+                    // Powers of -1, 0 and 1 stay small whatever the exponent, and
+                    // the result has one bit more than its log2, rounded down
+                    .filter(|&exponent| {
+                        base_bits <= 0.0 || exponent as f64 * base_bits < MAX_POW_BITS
+                    })
+                    .and_then(|exponent| num_traits::checked_pow(left, exponent))
+            }
+            Self::Min => Some(left.min(right)),
+            Self::Max => Some(left.max(right)),
+        };
+
+        result.ok_or(HomeworkError::Overflow)
+    }
+
+    /// How the operator is written in a worksheet. Min and max are written as
+    /// single characters so they fit in the cephalopod layout.
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mult => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Pow => "^",
+            Self::Min => "<",
+            Self::Max => ">",
+        }
+    }
+
+    /// How tightly the operator binds in an infix expression
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Min | Self::Max => 0,
+            Self::Add | Self::Sub => 1,
+            Self::Mult | Self::Div | Self::Rem => 2,
+            Self::Pow => 3,
+        }
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Operation {
+    #[getter(symbol)]
+    fn get_symbol(&self) -> &'static str {
+        self.symbol()
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day06")]
+#[derive(PartialEq, Clone, Debug)]
 struct Problem {
+    #[pyo3(get)]
     values: Vec<isize>,
-    operation: Operation,
+
+    #[pyo3(get)]
+    /// Either a single operator combining all of the values from left to
+    /// right, or one operator between each pair of values to be evaluated as
+    /// an infix expression
+    operators: Vec<Operation>,
+
+    #[pyo3(get)]
+    /// Half-open (start, end) range of the columns of the worksheet the
+    /// problem was written in
+    span: (usize, usize),
+}
+
+/// Apply an operator to the top two operands of the stack
+fn reduce<N: Integer>(operands: &mut Vec<N>, operator: Operation) -> Result<(), HomeworkError> {
+    let right = operands.pop().unwrap();
+    let left = operands.pop().unwrap();
+    operands.push(operator.apply(left, right)?);
+
+    Ok(())
 }
 
 impl Problem {
-    fn solve(&self) -> isize {
-        match self.operation {
-            Operation::Add => self.values.iter().sum(),
-            Operation::Mult => self.values.iter().product(),
+    fn solve<N: Integer>(&self) -> Result<N, HomeworkError> {
+        let Some((&first, rest)) = self.values.split_first() else {
+            return Err(HomeworkError::NoValues);
+        };
+
+        match self.operators[..] {
+            [operator] => rest.iter().try_fold(N::from(first), |total, &value| {
+                operator.apply(total, N::from(value))
+            }),
+            _ if self.operators.len() == rest.len() => self.evaluate_infix(),
+            _ => Err(HomeworkError::MismatchedOperators {
+                values: self.values.len(),
+                operators: self.operators.len(),
+            }),
+        }
+    }
+
+    /// Evaluate the values and operators as an infix expression with the
+    /// usual precedence, where ``^`` is right associative
+    fn evaluate_infix<N: Integer>(&self) -> Result<N, HomeworkError> {
+        let mut operands = vec![N::from(self.values[0])];
+        let mut pending: Vec<Operation> = Vec::new();
+
+        for (&operator, &value) in self.operators.iter().zip(&self.values[1..]) {
+            while let Some(&previous) = pending.last() {
+                let binds_tighter = previous.precedence() > operator.precedence()
+                    || (previous.precedence() == operator.precedence()
+                        && operator != Operation::Pow);
+                if !binds_tighter {
+                    break;
+                }
+                reduce(&mut operands, previous)?;
+                pending.pop();
+            }
+            pending.push(operator);
+            operands.push(N::from(value));
+        }
+        while let Some(operator) = pending.pop() {
+            reduce(&mut operands, operator)?;
+        }
+
+        Ok(operands.pop().unwrap())
+    }
+
+    /// Exact result, only falling back to arbitrary precision if machine
+    /// integers overflow
+    fn exact_solution(&self) -> Result<BigInt, HomeworkError> {
+        match self.solve::<isize>() {
+            Err(HomeworkError::Overflow) => self.solve(),
+            solution => solution.map(BigInt::from),
         }
     }
 }
 
+#[gen_stub_pymethods]
+#[pymethods]
+impl Problem {
+    /// The problem's result as an exact integer
+    #[pyo3(name = "solve")]
+    fn py_solve<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.exact_solution()?.into_pyobject(py)
+    }
+
+    fn __repr__(&self) -> String {
+        let symbols: Vec<&str> = self.operators.iter().map(|o| o.symbol()).collect();
+        format!(
+            "Problem(values={:?}, operators={:?}, span={:?})",
+            self.values, symbols, self.span
+        )
+    }
+}
+
+/// Whitespace-separated tokens in a line, with the half-open (start, end)
+/// range of columns each one occupies
+fn tokens(line: &str) -> Vec<((usize, usize), &str)> {
+    let mut tokens = Vec::new();
+    // (column, byte index) of the start of the current token
+    let mut start = None;
+
+    for (col, (index, c)) in line.char_indices().chain([(line.len(), ' ')]).enumerate() {
+        match start {
+            None if !c.is_whitespace() => start = Some((col, index)),
+            Some((start_col, start_index)) if c.is_whitespace() => {
+                tokens.push(((start_col, col), &line[start_index..index]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// Columns between tab stops when expanding tabs in a worksheet
+const TAB_WIDTH: usize = 8;
+
+/// A line with each tab replaced by spaces up to the next tab stop, so that
+/// every character occupies a single column
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut col = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let next = (col / TAB_WIDTH + 1) * TAB_WIDTH;
+            expanded.extend(std::iter::repeat_n(' ', next - col));
+            col = next;
+        } else {
+            expanded.push(c);
+            col += 1;
+        }
+    }
+
+    expanded
+}
+
+/// Characters of each line of a worksheet with tabs expanded, and short lines
+/// padded with spaces to the width of the longest
+fn char_grid(value: &str) -> Vec<Vec<char>> {
+    let mut lines: Vec<Vec<char>> = value
+        .lines()
+        .map(|line| expand_tabs(line).chars().collect())
+        .collect();
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+    for line in &mut lines {
+        line.resize(width, ' ');
+    }
+
+    lines
+}
+
+/// Smallest span covering all of the given spans
+fn covering_span(spans: impl IntoIterator<Item = (usize, usize)>) -> (usize, usize) {
+    spans
+        .into_iter()
+        .reduce(|(start, end), (other_start, other_end)| {
+            (start.min(other_start), end.max(other_end))
+        })
+        .unwrap_or((0, 0))
+}
+
+fn parse_value(value: &str) -> Result<isize, HomeworkError> {
+    value
+        .parse()
+        .map_err(|_| HomeworkError::InvalidValue(value.to_owned()))
+}
+
+/// How problems are laid out in a worksheet
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day06")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Layout {
+    /// Each problem is a column of whitespace-separated values with its
+    /// operator on the last line
+    Rows,
+    /// Each value is written down a column of digits, with the problems and
+    /// their values read from left to right, and each problem's operator on
+    /// the last line
+    Columns,
+    /// As ``Columns``, but problems and their values are read from right to
+    /// left
+    RightToLeft,
+    /// Each value is written along a line, with problems separated by blank
+    /// lines and read from top to bottom, and each problem's operator in the
+    /// last column. Problem spans are ranges of lines rather than columns.
+    TopToBottom,
+    /// Each problem is a column of whitespace-separated tokens alternating
+    /// between values and operators, evaluated as an infix expression
+    Expressions,
+    /// Whichever of ``Rows`` or ``Columns`` reads the worksheet validly
+    Auto,
+}
+
+/// Half-open (start, end) ranges of the columns between the columns that are
+/// blank on every line, treating short lines as padded with spaces
+fn separated_blocks(lines: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let blank = |col: usize| {
+        lines
+            .iter()
+            .all(|line| line.get(col).is_none_or(|c| c.is_whitespace()))
+    };
+
+    let mut blocks = Vec::new();
+    let mut start = None;
+    for col in 0..=width {
+        match start {
+            None if col < width && !blank(col) => start = Some(col),
+            Some(block_start) if col == width || blank(col) => {
+                blocks.push((block_start, col));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
 #[gen_stub_pyclass]
 #[pyclass(module = "aoc_2025.rs.day06")]
 #[derive(PartialEq, Debug)]
@@ -38,108 +519,1018 @@ struct Homework {
     problems: Vec<Problem>,
 }
 
-#[gen_stub_pymethods]
-#[pymethods]
 impl Homework {
-    #[classmethod]
-    #[pyo3(signature = (value))]
-    fn read_wrong(_cls: &Bound<'_, PyType>, value: &str) -> Self {
-        let mut lines: Vec<&str> = value.lines().collect();
+    /// Read problems as columns of whitespace-separated values, with each
+    /// problem's operator on the last line
+    fn parse_rows(value: &str) -> Result<Self, HomeworkError> {
+        let expanded: Vec<String> = value.lines().map(expand_tabs).collect();
+        let mut lines: Vec<Vec<((usize, usize), &str)>> =
+            expanded.iter().map(|line| tokens(line)).collect();
 
-        let operation_line = lines.pop().unwrap();
+        let operation_line = lines.pop().ok_or(HomeworkError::NoValues)?;
+        if lines.is_empty() {
+            return Err(HomeworkError::NoValues);
+        }
+        let expected = lines[0].len();
+        if let Some((index, line)) = lines
+            .iter()
+            .enumerate()
+            .find(|(_, line)| line.len() != expected)
+        {
+            return Err(HomeworkError::MisalignedLine {
+                line: index + 1,
+                expected,
+                found: line.len(),
+            });
+        }
+        if operation_line.len() != expected {
+            return Err(HomeworkError::OperatorCount {
+                problems: expected,
+                operators: operation_line.len(),
+            });
+        }
+
+        let problems = (0..expected)
+            .zip(operation_line)
+            .map(|(col, (operation_span, operation))| {
+                let column: Vec<((usize, usize), &str)> =
+                    lines.iter().map(|line| line[col]).collect();
+                Ok(Problem {
+                    values: column
+                        .iter()
+                        .map(|&(_, value)| parse_value(value))
+                        .collect::<Result<_, _>>()?,
+                    operators: vec![Operation::try_from(operation)?],
+                    span: covering_span(
+                        column.iter().map(|&(span, _)| span).chain([operation_span]),
+                    ),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { problems })
+    }
+
+    /// Read problems written in columns of digits, with each problem's
+    /// operator on the last line under one of its columns
+    fn parse_columns(value: &str) -> Result<Self, HomeworkError> {
+        let grid = char_grid(value);
+        let (operator_line, value_lines) = grid.split_last().ok_or(HomeworkError::NoValues)?;
+        let width = operator_line.len();
+
+        // Values of each problem, along with the span of columns they're in
+        let mut problem_values: Vec<((usize, usize), Vec<isize>)> = Vec::new();
+        let mut current: Option<(usize, Vec<isize>)> = None;
+        for col in 0..=width {
+            let digits: String = value_lines
+                .iter()
+                .filter_map(|line| line.get(col))
+                .collect();
+            let digits = digits.trim();
+            if digits.is_empty() {
+                if let Some((start, values)) = current.take() {
+                    problem_values.push(((start, col), values));
+                }
+            } else {
+                current
+                    .get_or_insert_with(|| (col, Vec::new()))
+                    .1
+                    .push(parse_value(digits)?);
+            }
+        }
+        if problem_values.is_empty() {
+            return Err(HomeworkError::NoValues);
+        }
 
-        let transposed: Vec<Vec<isize>> = lines
+        let operators: Vec<(usize, Operation)> = operator_line
             .iter()
-            .map(|&line| {
-                line.split_whitespace()
-                    .map(|v| v.parse().unwrap())
-                    .collect::<Vec<_>>()
+            .enumerate()
+            .filter(|(_, c)| !c.is_whitespace())
+            .map(|(col, c)| Ok((col, Operation::try_from(c.to_string().as_str())?)))
+            .collect::<Result<_, HomeworkError>>()?;
+        if operators.len() != problem_values.len() {
+            return Err(HomeworkError::OperatorCount {
+                problems: problem_values.len(),
+                operators: operators.len(),
+            });
+        }
+
+        let problems = problem_values
+            .into_iter()
+            .zip(operators)
+            .map(|((span, values), (col, operation))| {
+                if col < span.0 || col >= span.1 {
+                    return Err(HomeworkError::MisplacedOperator(span));
+                }
+                Ok(Problem {
+                    values,
+                    operators: vec![operation],
+                    span,
+                })
             })
-            .collect();
-        if transposed.is_empty() {
-            panic!("No problem values");
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { problems })
+    }
+
+    /// Read problems as columns of whitespace-separated tokens, where each
+    /// column alternates between values and operators to form an infix
+    /// expression. Each problem sits in its own block of columns between
+    /// columns that are blank on every line, so lines can skip problems.
+    fn parse_expressions(value: &str) -> Result<Self, HomeworkError> {
+        let blocks = separated_blocks(&char_grid(value));
+        if blocks.is_empty() {
+            return Err(HomeworkError::NoValues);
         }
 
-        let problem_values: Vec<Vec<isize>> = (0..transposed[0].len())
-            .map(|col| {
-                (0..transposed.len())
-                    .map(|row| transposed[row][col])
-                    .collect()
+        let expanded: Vec<String> = value.lines().map(expand_tabs).collect();
+        let mut columns: Vec<Vec<((usize, usize), &str)>> = vec![Vec::new(); blocks.len()];
+        // Index of the line each problem last had a token on
+        let mut last_lines = vec![None; blocks.len()];
+        for (line, row) in expanded.iter().enumerate() {
+            for (span, token) in tokens(row) {
+                // Tokens never contain blank columns, so always sit in a block
+                let block = blocks
+                    .iter()
+                    .position(|&(start, end)| start <= span.0 && span.1 <= end)
+                    .unwrap();
+                if last_lines[block].replace(line) == Some(line) {
+                    return Err(HomeworkError::CrowdedLine {
+                        line: line + 1,
+                        span: blocks[block],
+                    });
+                }
+                columns[block].push((span, token));
+            }
+        }
+
+        let problems = columns
+            .into_iter()
+            .map(|column| {
+                let (spans, tokens): (Vec<(usize, usize)>, Vec<&str>) = column.into_iter().unzip();
+                if tokens.len().is_multiple_of(2) {
+                    return Err(HomeworkError::MismatchedOperators {
+                        values: tokens.len().div_ceil(2),
+                        operators: tokens.len() / 2,
+                    });
+                }
+
+                Ok(Problem {
+                    values: tokens
+                        .iter()
+                        .step_by(2)
+                        .map(|&value| parse_value(value))
+                        .collect::<Result<_, _>>()?,
+                    operators: tokens
+                        .iter()
+                        .skip(1)
+                        .step_by(2)
+                        .map(|&operator| Operation::try_from(operator))
+                        .collect::<Result<_, _>>()?,
+                    span: covering_span(spans),
+                })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        let operations = operation_line.split_whitespace().map(|v| match v {
-            "+" => Operation::Add,
-            "*" => Operation::Mult,
-            _ => panic!("Invalid operation"),
-        });
+        Ok(Self { problems })
+    }
 
-        Self {
-            problems: problem_values
-                .into_iter()
-                .zip(operations)
-                .map(|(values, operation)| Problem { values, operation })
-                .collect(),
+    /// Read problems in the given layout
+    fn parse(value: &str, layout: Layout) -> Result<Self, HomeworkError> {
+        match layout {
+            Layout::Rows => Self::parse_rows(value),
+            Layout::Columns => Self::parse_columns(value),
+            Layout::RightToLeft => {
+                let mut homework = Self::parse_columns(value)?;
+                homework.problems.reverse();
+                for problem in &mut homework.problems {
+                    problem.values.reverse();
+                }
+                Ok(homework)
+            }
+            Layout::TopToBottom => {
+                let lines = char_grid(value);
+                let width = lines.first().map_or(0, Vec::len);
+                let transposed: Vec<String> = (0..width)
+                    .map(|col| lines.iter().map(|line| line[col]).collect())
+                    .collect();
+                Self::parse_columns(&transposed.join("\n"))
+            }
+            Layout::Expressions => Self::parse_expressions(value),
+            Layout::Auto => Self::parse_auto(value),
         }
     }
 
-    #[classmethod]
-    #[pyo3(signature = (value))]
-    fn read_right(_cls: &Bound<'_, PyType>, value: &str) -> Self {
-        let transposed_chars: Vec<Vec<char>> =
-            value.lines().map(|line| line.chars().collect()).collect();
-        if transposed_chars.is_empty() {
-            panic!("No problem values");
-        }
-        let (numerical_input, operation_input): (Vec<String>, Vec<String>) =
-            (0..transposed_chars[0].len())
-                .map(|col| {
-                    let str = (0..transposed_chars.len())
-                        .map(|row| transposed_chars[row][col])
-                        .collect::<String>();
-                    let (num_part, op_part) = str.split_at(str.len() - 1);
-                    (num_part.to_owned(), op_part.to_owned())
-                })
+    /// Read problems in whichever of the row or column layouts fits the
+    /// worksheet, where each problem has to sit in its own block of columns
+    /// between columns that are blank on every line
+    fn parse_auto(value: &str) -> Result<Self, HomeworkError> {
+        let lines = char_grid(value);
+        let blocks = separated_blocks(&lines);
+        let block_of = |col: usize| {
+            blocks
+                .iter()
+                .position(|&(start, end)| start <= col && col < end)
+        };
+
+        // The operator line has exactly one operator in each block
+        let fits_operators = lines.last().is_some_and(|operator_line| {
+            let operator_blocks: Vec<Option<usize>> = operator_line
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| !c.is_whitespace())
+                .map(|(col, _)| block_of(col))
                 .collect();
-        let operations: Vec<Operation> = operation_input
-            .into_iter()
-            .filter_map(|s| {
-                if s.trim().is_empty() {
-                    None
-                } else {
-                    match s.as_str() {
-                        "+" => Some(Operation::Add),
-                        "*" => Some(Operation::Mult),
-                        _ => panic!("Invalid operator"),
-                    }
-                }
+            operator_blocks.len() == blocks.len()
+                && operator_blocks.windows(2).all(|pair| pair[0] < pair[1])
+        });
+        if !fits_operators {
+            return Err(HomeworkError::UnrecognisedLayout);
+        }
+
+        // Every line has at most one token in each block
+        let fits_rows = value.lines().all(|line| {
+            let token_blocks: Vec<Option<usize>> = tokens(&expand_tabs(line))
+                .iter()
+                .map(|&((start, _), _)| block_of(start))
+                .collect();
+            token_blocks.windows(2).all(|pair| pair[0] < pair[1])
+        });
+
+        let rows = Self::parse_rows(value).ok().filter(|_| fits_rows);
+        let columns = Self::parse_columns(value).ok();
+        match (rows, columns) {
+            (Some(rows), Some(columns)) if rows == columns => Ok(rows),
+            (Some(_), Some(_)) => Err(HomeworkError::AmbiguousLayout(vec![
+                Layout::Rows,
+                Layout::Columns,
+            ])),
+            (Some(homework), None) | (None, Some(homework)) => Ok(homework),
+            (None, None) => Err(HomeworkError::UnrecognisedLayout),
+        }
+    }
+
+    /// Lines writing each problem as a column of right-aligned tokens, either
+    /// its values followed by its operator, or alternating values and
+    /// operators as an infix expression
+    fn token_lines(&self, layout: Layout) -> Result<Vec<String>, HomeworkError> {
+        let columns: Vec<Vec<String>> = self
+            .problems
+            .iter()
+            .enumerate()
+            .map(|(problem, p)| match layout {
+                Layout::Rows if p.operators.len() == 1 && !p.values.is_empty() => Ok(p
+                    .values
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain([p.operators[0].symbol().to_owned()])
+                    .collect()),
+                Layout::Expressions if p.operators.len() + 1 == p.values.len() => Ok(p
+                    .values
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, value)| {
+                        let operator = i.checked_sub(1).map(|i| p.operators[i].symbol().to_owned());
+                        operator.into_iter().chain([value.to_string()])
+                    })
+                    .collect()),
+                _ => Err(HomeworkError::UnrenderableProblem { layout, problem }),
             })
+            .collect::<Result<_, _>>()?;
+        // Every line needs a token for every problem to keep them aligned
+        let height = columns[0].len();
+        if let Some(problem) = columns.iter().position(|column| column.len() != height) {
+            return Err(HomeworkError::UnrenderableProblem { layout, problem });
+        }
+
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|column| column.iter().map(String::len).max().unwrap_or(0))
             .collect();
-        let (mut problem_values, last) = numerical_input.into_iter().fold(
-            (Vec::<Vec<isize>>::new(), Vec::<isize>::new()),
-            |(mut result, mut current_problem), val| {
-                let trimmed = val.trim();
-                if trimmed.is_empty() {
-                    result.push(current_problem);
-                    current_problem = Vec::new();
+        Ok((0..height)
+            .map(|row| {
+                columns
+                    .iter()
+                    .zip(&widths)
+                    .map(|(column, &width)| format!("{:>width$}", column[row]))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect())
+    }
+
+    /// Grid writing each value down a column of digits, with a blank column
+    /// between problems and each problem's operator on the last line under
+    /// its first column. Right to left layouts write problems and values in
+    /// reverse.
+    fn column_grid(&self, layout: Layout) -> Result<Vec<Vec<char>>, HomeworkError> {
+        let mut problems: Vec<(usize, &Problem)> = self.problems.iter().enumerate().collect();
+        if layout == Layout::RightToLeft {
+            problems.reverse();
+        }
+        let digits: Vec<Vec<Vec<char>>> = problems
+            .iter()
+            .map(|&(problem, p)| {
+                if p.operators.len() != 1 || p.values.is_empty() {
+                    return Err(HomeworkError::UnrenderableProblem { layout, problem });
+                }
+                let mut values: Vec<Vec<char>> = p
+                    .values
+                    .iter()
+                    .map(|value| value.to_string().chars().collect())
+                    .collect();
+                if layout == Layout::RightToLeft {
+                    values.reverse();
+                }
+                Ok(values)
+            })
+            .collect::<Result<_, _>>()?;
+        let height = digits.iter().flatten().map(Vec::len).max().unwrap_or(0);
+
+        let mut grid = vec![Vec::new(); height + 1];
+        for (i, ((_, problem), values)) in problems.iter().zip(digits).enumerate() {
+            if i > 0 {
+                grid.iter_mut().for_each(|line| line.push(' '));
+            }
+            for (col, value) in values.iter().enumerate() {
+                for (row, line) in grid[..height].iter_mut().enumerate() {
+                    line.push(value.get(row).copied().unwrap_or(' '));
+                }
+                if col == 0 {
+                    grid[height].extend(problem.operators[0].symbol().chars());
                 } else {
-                    current_problem.push(trimmed.parse().unwrap());
+                    grid[height].push(' ');
                 }
-                (result, current_problem)
-            },
-        );
-        problem_values.push(last);
+            }
+        }
 
-        Self {
-            problems: problem_values
-                .into_iter()
-                .zip(operations)
-                .map(|(values, operation)| Problem { values, operation })
+        Ok(grid)
+    }
+
+    /// The worksheet written out in the given layout, such that reading it
+    /// back in that layout gives the same values and operators
+    fn to_text(&self, layout: Layout) -> Result<String, HomeworkError> {
+        if self.problems.is_empty() {
+            return Err(HomeworkError::NoValues);
+        }
+        let lines: Vec<String> = match layout {
+            Layout::Rows | Layout::Expressions => self.token_lines(layout)?,
+            Layout::Columns | Layout::RightToLeft => self
+                .column_grid(layout)?
+                .iter()
+                .map(|line| line.iter().collect())
                 .collect(),
+            Layout::TopToBottom => {
+                let grid = self.column_grid(layout)?;
+                (0..grid[0].len())
+                    .map(|col| grid.iter().map(|line| line[col]).collect())
+                    .collect()
+            }
+            Layout::Auto => return Err(HomeworkError::UnrenderableLayout(layout)),
+        };
+
+        Ok(lines.iter().map(|line| format!("{line}\n")).collect())
+    }
+
+    /// Sum of the problems' results, solved with integer type ``N``
+    fn total<N: Integer>(&self) -> Result<N, HomeworkError> {
+        self.problems.iter().try_fold(N::zero(), |total, p| {
+            Operation::Add.apply(total, p.solve()?)
+        })
+    }
+
+    /// Exact sum of the problems' results, only falling back to arbitrary
+    /// precision if machine integers overflow
+    fn exact_total(&self) -> Result<BigInt, HomeworkError> {
+        match self.total::<isize>() {
+            Err(HomeworkError::Overflow) => self.total(),
+            total => total.map(BigInt::from),
         }
     }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Homework {
+    #[classmethod]
+    #[pyo3(signature = (value))]
+    fn read_wrong(_cls: &Bound<'_, PyType>, value: &str) -> PyResult<Self> {
+        Ok(Self::parse_rows(value)?)
+    }
+
+    #[classmethod]
+    #[pyo3(signature = (value))]
+    fn read_right(_cls: &Bound<'_, PyType>, value: &str) -> PyResult<Self> {
+        Ok(Self::parse_columns(value)?)
+    }
+
+    /// Read a worksheet where each column is an infix expression, with values
+    /// and operators on alternating lines, evaluated with the usual precedence
+    #[classmethod]
+    #[pyo3(signature = (value))]
+    fn read_expressions(_cls: &Bound<'_, PyType>, value: &str) -> PyResult<Self> {
+        Ok(Self::parse_expressions(value)?)
+    }
+
+    /// Read a worksheet in the given layout, working out whether it's written
+    /// in rows or columns if no layout is given
+    #[classmethod]
+    #[pyo3(name = "parse", signature = (value, layout=None))]
+    fn py_parse(_cls: &Bound<'_, PyType>, value: &str, layout: Option<Layout>) -> PyResult<Self> {
+        Ok(Self::parse(value, layout.unwrap_or(Layout::Auto))?)
+    }
+
+    /// The worksheet written out in the given layout, which reads back in
+    /// that layout as the same problems
+    #[pyo3(name = "to_text")]
+    fn py_to_text(&self, layout: Layout) -> PyResult<String> {
+        Ok(self.to_text(layout)?)
+    }
+
+    /// Sum of the problems' results as an exact integer
+    fn grand_total<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.exact_total()?.into_pyobject(py)
+    }
+
+    /// Sum of the problems' results, raising an error if any step overflows a
+    /// 64-bit integer
+    fn checked_grand_total(&self) -> PyResult<isize> {
+        Ok(self.total()?)
+    }
 
-    fn grand_total(&self) -> isize {
-        self.problems.iter().map(|p| p.solve()).sum()
+    /// The problems in the order they're written
+    #[getter]
+    fn problems(&self) -> Vec<Problem> {
+        self.problems.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.problems.len()
+    }
+
+    fn __iter__(&self) -> ProblemIterator {
+        ProblemIterator {
+            problems: self.problems.clone().into_iter(),
+        }
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day06")]
+struct ProblemIterator {
+    problems: std::vec::IntoIter<Problem>,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl ProblemIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Problem> {
+        self.problems.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    const EXAMPLE: &str = include_str!("../../../data/test/d6");
+
+    #[rstest]
+    fn test_read_wrong() {
+        assert_eq!(
+            Homework::parse_rows(EXAMPLE).unwrap().total::<isize>(),
+            Ok(4277556)
+        );
+    }
+
+    #[rstest]
+    fn test_read_right() {
+        assert_eq!(
+            Homework::parse_columns(EXAMPLE).unwrap().total::<isize>(),
+            Ok(3263827)
+        );
+    }
+
+    #[rstest]
+    fn test_ragged_lines() {
+        let ragged: String = EXAMPLE
+            .lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            Homework::parse_columns(&ragged),
+            Homework::parse_columns(EXAMPLE)
+        );
+        assert_eq!(Homework::parse_rows(&ragged), Homework::parse_rows(EXAMPLE));
+    }
+
+    #[rstest]
+    #[case(Layout::Rows, "1\t2\n3\t4\n+\t*", 12, (8, 9))]
+    #[case(Layout::Columns, "1\t2\n3\t4\n+\t*", 37, (8, 9))]
+    #[case(Layout::Columns, "12\t3\n4\t5\n+\t*", 51, (8, 9))]
+    #[case(Layout::Expressions, "1\t2\n+\t*\n3\t4", 12, (8, 9))]
+    fn test_tabs(
+        #[case] layout: Layout,
+        #[case] worksheet: &str,
+        #[case] expected: isize,
+        #[case] last_span: (usize, usize),
+    ) {
+        let homework = Homework::parse(worksheet, layout).unwrap();
+        assert_eq!(homework.total::<isize>(), Ok(expected));
+        assert_eq!(homework.problems.last().unwrap().span, last_span);
+    }
+
+    #[rstest]
+    #[case(Layout::Rows, "", HomeworkError::NoValues)]
+    #[case(Layout::Rows, "+ *", HomeworkError::NoValues)]
+    #[case(
+        Layout::Rows,
+        "1 2\n3\n+ *",
+        HomeworkError::MisalignedLine { line: 2, expected: 2, found: 1 }
+    )]
+    #[case(
+        Layout::Rows,
+        "1 2\n3 4\n+",
+        HomeworkError::OperatorCount { problems: 2, operators: 1 }
+    )]
+    #[case(
+        Layout::Rows,
+        "1 2\n3 4\n+ * -",
+        HomeworkError::OperatorCount { problems: 2, operators: 3 }
+    )]
+    #[case(Layout::Rows, "1 x\n3 4\n+ *", HomeworkError::InvalidValue("x".to_owned()))]
+    #[case(Layout::Rows, "1 2\n3 4\n+ é", HomeworkError::UnknownOperator("é".to_owned()))]
+    #[case(Layout::Columns, "", HomeworkError::NoValues)]
+    #[case(Layout::Columns, "+", HomeworkError::NoValues)]
+    #[case(Layout::Columns, "\n\n+", HomeworkError::NoValues)]
+    #[case(
+        Layout::Columns,
+        "1 2\n3 4\n+",
+        HomeworkError::OperatorCount { problems: 2, operators: 1 }
+    )]
+    #[case(
+        Layout::Columns,
+        "12\n34\n++",
+        HomeworkError::OperatorCount { problems: 1, operators: 2 }
+    )]
+    #[case(
+        Layout::Columns,
+        "1 2\n3 4\n ++",
+        HomeworkError::MisplacedOperator((0, 1))
+    )]
+    #[case(Layout::Columns, "é\n+", HomeworkError::InvalidValue("é".to_owned()))]
+    #[case(Layout::Columns, "1\n+é", HomeworkError::UnknownOperator("é".to_owned()))]
+    #[case(Layout::Columns, "1x\n++", HomeworkError::InvalidValue("x".to_owned()))]
+    #[case(Layout::TopToBottom, "", HomeworkError::NoValues)]
+    #[case(Layout::TopToBottom, "12+\n\n3", HomeworkError::OperatorCount { problems: 2, operators: 1 })]
+    #[case(Layout::Expressions, "", HomeworkError::NoValues)]
+    #[case(Layout::Auto, "", HomeworkError::UnrecognisedLayout)]
+    #[case(Layout::Auto, "1 2\n\t3\n+", HomeworkError::UnrecognisedLayout)]
+    fn test_malformed(
+        #[case] layout: Layout,
+        #[case] worksheet: &str,
+        #[case] expected: HomeworkError,
+    ) {
+        assert_eq!(Homework::parse(worksheet, layout), Err(expected));
+    }
+
+    #[rstest]
+    #[case(Operation::Sub, 3, 5, Ok(-2))]
+    #[case(Operation::Div, 12, 4, Ok(3))]
+    #[case(
+        Operation::Div,
+        12,
+        5,
+        Err(HomeworkError::InexactDivision("12".to_owned(), "5".to_owned()))
+    )]
+    #[case(Operation::Div, 12, 0, Err(HomeworkError::DivisionByZero))]
+    #[case(Operation::Rem, 12, 5, Ok(2))]
+    #[case(Operation::Rem, 12, 0, Err(HomeworkError::DivisionByZero))]
+    #[case(Operation::Pow, 2, 10, Ok(1024))]
+    #[case(Operation::Pow, -1, isize::MAX, Ok(-1))]
+    #[case(
+        Operation::Pow,
+        2,
+        -1,
+        Err(HomeworkError::NegativeExponent("-1".to_owned()))
+    )]
+    #[case(Operation::Min, 2, -1, Ok(-1))]
+    #[case(Operation::Max, 2, -1, Ok(2))]
+    fn test_apply(
+        #[case] operation: Operation,
+        #[case] left: isize,
+        #[case] right: isize,
+        #[case] expected: Result<isize, HomeworkError>,
+    ) {
+        assert_eq!(operation.apply(left, right), expected);
+        assert_eq!(
+            operation.apply(BigInt::from(left), BigInt::from(right)),
+            expected.map(BigInt::from)
+        );
+    }
+
+    #[rstest]
+    #[case(Operation::Add, isize::MAX, 1, "9223372036854775808")]
+    #[case(Operation::Sub, isize::MIN, 1, "-9223372036854775809")]
+    #[case(Operation::Mult, isize::MAX, 2, "18446744073709551614")]
+    #[case(Operation::Div, isize::MIN, -1, "9223372036854775808")]
+    #[case(Operation::Rem, isize::MIN, -1, "0")]
+    #[case(Operation::Pow, 2, 64, "18446744073709551616")]
+    fn test_apply_overflow(
+        #[case] operation: Operation,
+        #[case] left: isize,
+        #[case] right: isize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(operation.apply(left, right), Err(HomeworkError::Overflow));
+        assert_eq!(
+            operation.apply(BigInt::from(left), BigInt::from(right)),
+            Ok(expected.parse().unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case("min", Ok(Operation::Min))]
+    #[case(">", Ok(Operation::Max))]
+    #[case("&", Err(HomeworkError::UnknownOperator("&".to_owned())))]
+    fn test_operation_from_str(
+        #[case] value: &str,
+        #[case] expected: Result<Operation, HomeworkError>,
+    ) {
+        assert_eq!(Operation::try_from(value), expected);
+    }
+
+    #[rstest]
+    #[case("2\n+\n3\n*\n4", 14)]
+    #[case("2\n*\n3\n+\n4", 10)]
+    #[case("20\n-\n3\n-\n4", 13)]
+    #[case("2\n^\n3\n^\n2", 512)]
+    #[case("64\n/\n4\n/\n2", 8)]
+    #[case("1\n+\n7\n<\n3\n*\n2", 6)]
+    #[case("7", 7)]
+    fn test_infix_precedence(#[case] worksheet: &str, #[case] expected: isize) {
+        assert_eq!(
+            Homework::parse_expressions(worksheet)
+                .unwrap()
+                .total::<isize>(),
+            Ok(expected)
+        );
+    }
+
+    #[rstest]
+    fn test_read_expressions_columns() {
+        let worksheet = "2  10\n+  %\n3  4\n*\n4";
+        let homework = Homework::parse_expressions(worksheet).unwrap();
+        assert_eq!(homework.problems[1].values, vec![10, 4]);
+        assert_eq!(homework.total::<isize>(), Ok(14 + 2));
+
+        // Lines can leave out earlier problems
+        let worksheet = "2 10\n+ %\n3 4\n  *\n  5";
+        let homework = Homework::parse_expressions(worksheet).unwrap();
+        assert_eq!(homework.problems[0].values, vec![2, 3]);
+        assert_eq!(homework.problems[1].values, vec![10, 4, 5]);
+        assert_eq!(homework.total::<isize>(), Ok(5 + 10));
+    }
+
+    #[rstest]
+    #[case("2\n+", HomeworkError::MismatchedOperators { values: 1, operators: 1 })]
+    #[case("2\n&\n3", HomeworkError::UnknownOperator("&".to_owned()))]
+    #[case("two\n+\n3", HomeworkError::InvalidValue("two".to_owned()))]
+    #[case("", HomeworkError::NoValues)]
+    #[case("100 2\n+ %\n1 1", HomeworkError::CrowdedLine { line: 2, span: (0, 3) })]
+    fn test_read_expressions_errors(#[case] worksheet: &str, #[case] expected: HomeworkError) {
+        assert_eq!(Homework::parse_expressions(worksheet), Err(expected));
+    }
+
+    #[rstest]
+    fn test_unknown_operators() {
+        assert_eq!(
+            Homework::parse_rows("1 2\n3 4\n+ &"),
+            Err(HomeworkError::UnknownOperator("&".to_owned()))
+        );
+        assert_eq!(
+            Homework::parse_columns("1 2\n3 4\n+ ?"),
+            Err(HomeworkError::UnknownOperator("?".to_owned()))
+        );
+    }
+
+    #[rstest]
+    fn test_new_operators_read_right() {
+        let homework = Homework::parse_columns("12 4 \n 3 21\n-  % ").unwrap();
+        // 1 - 23 and 42 % 1
+        assert_eq!(homework.total::<isize>(), Ok(-22));
+    }
+
+    #[rstest]
+    fn test_mismatched_operators() {
+        let problem = Problem {
+            values: vec![1, 2, 3, 4],
+            operators: vec![Operation::Add, Operation::Mult],
+            span: (0, 1),
+        };
+        assert_eq!(
+            problem.solve::<isize>(),
+            Err(HomeworkError::MismatchedOperators {
+                values: 4,
+                operators: 2
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(2, 1_000_000_000)]
+    #[case(2, 65_536)]
+    #[case(-3, 50_000)]
+    #[case(isize::MAX, 1 << 20)]
+    fn test_huge_powers(#[case] base: isize, #[case] exponent: isize) {
+        assert_eq!(
+            Operation::Pow.apply(BigInt::from(base), BigInt::from(exponent)),
+            Err(HomeworkError::Overflow)
+        );
+        let homework = Homework::parse_rows(&format!("{base}\n{exponent}\n^")).unwrap();
+        assert_eq!(homework.exact_total(), Err(HomeworkError::Overflow));
+    }
+
+    #[rstest]
+    fn test_largest_power() {
+        assert_eq!(
+            Operation::Pow.apply(BigInt::from(2), BigInt::from(65_535)),
+            Ok(BigInt::from(2).pow(65_535))
+        );
+    }
+
+    #[rstest]
+    fn test_zero_power_of_huge_base() {
+        let huge = BigInt::from(2).pow(1100);
+        assert_eq!(
+            Operation::Pow.apply(huge, BigInt::zero()),
+            Ok(BigInt::one())
+        );
+        let homework = Homework::parse_rows("2\n1100\n0\n^").unwrap();
+        assert_eq!(homework.exact_total(), Ok(BigInt::one()));
+    }
+
+    #[rstest]
+    fn test_big_results() {
+        let homework =
+            Homework::parse_rows("9223372036854775807 3\n9223372036854775807 2\n* ^").unwrap();
+        assert_eq!(homework.total::<isize>(), Err(HomeworkError::Overflow));
+
+        let expected = BigInt::from(isize::MAX) * BigInt::from(isize::MAX) + 9u32;
+        assert_eq!(homework.total::<BigInt>(), Ok(expected.clone()));
+        assert_eq!(homework.exact_total(), Ok(expected));
+    }
+
+    #[rstest]
+    fn test_big_results_keep_errors() {
+        let homework = Homework::parse_rows("9223372036854775807 3\n3 2\n* /").unwrap();
+        assert_eq!(
+            homework.exact_total(),
+            Err(HomeworkError::InexactDivision(
+                "3".to_owned(),
+                "2".to_owned()
+            ))
+        );
+    }
+
+    #[rstest]
+    fn test_tokens() {
+        assert_eq!(
+            tokens(" 12  +  3"),
+            vec![((1, 3), "12"), ((5, 6), "+"), ((8, 9), "3")]
+        );
+        assert_eq!(tokens("   "), vec![]);
+    }
+
+    #[rstest]
+    fn test_read_wrong_problems() {
+        let homework = Homework::parse_rows(EXAMPLE).unwrap();
+        assert_eq!(
+            homework.problems[0],
+            Problem {
+                values: vec![123, 45, 6],
+                operators: vec![Operation::Mult],
+                span: (0, 3),
+            }
+        );
+        assert_eq!(
+            homework.problems.iter().map(|p| p.span).collect::<Vec<_>>(),
+            vec![(0, 3), (4, 7), (8, 11), (12, 15)]
+        );
+    }
+
+    #[rstest]
+    fn test_read_right_problems() {
+        let homework = Homework::parse_columns(EXAMPLE).unwrap();
+        assert_eq!(
+            homework.problems[3],
+            Problem {
+                values: vec![623, 431, 4],
+                operators: vec![Operation::Add],
+                span: (12, 15),
+            }
+        );
+        assert_eq!(
+            homework.problems.iter().map(|p| p.span).collect::<Vec<_>>(),
+            vec![(0, 3), (4, 7), (8, 11), (12, 15)]
+        );
+    }
+
+    #[rstest]
+    fn test_read_expressions_spans() {
+        let homework = Homework::parse_expressions(" 2   10\n +   %\n33   4").unwrap();
+        assert_eq!(
+            homework.problems.iter().map(|p| p.span).collect::<Vec<_>>(),
+            vec![(0, 2), (5, 7)]
+        );
+    }
+
+    #[rstest]
+    fn test_exact_solution() {
+        let problem = Problem {
+            values: vec![isize::MAX, 2],
+            operators: vec![Operation::Mult],
+            span: (0, 19),
+        };
+        assert_eq!(
+            problem.exact_solution(),
+            Ok(BigInt::from(isize::MAX) * 2u32)
+        );
+    }
+
+    #[rstest]
+    fn test_separated_blocks() {
+        let lines: Vec<Vec<char>> = EXAMPLE.lines().map(|l| l.chars().collect()).collect();
+        assert_eq!(
+            separated_blocks(&lines),
+            vec![(0, 3), (4, 7), (8, 11), (12, 15)]
+        );
+    }
+
+    #[rstest]
+    fn test_right_to_left() {
+        let homework = Homework::parse("12 4 \n 3 21\n-  % ", Layout::RightToLeft).unwrap();
+        let values: Vec<Vec<isize>> = homework.problems.iter().map(|p| p.values.clone()).collect();
+        assert_eq!(values, vec![vec![1, 42], vec![23, 1]]);
+        // 1 % 42 and 23 - 1
+        assert_eq!(homework.total::<isize>(), Ok(23));
+    }
+
+    /// Values and operators of each problem, ignoring where they're written
+    fn contents(homework: &Homework) -> Vec<(Vec<isize>, Vec<Operation>)> {
+        homework
+            .problems
+            .iter()
+            .map(|p| (p.values.clone(), p.operators.clone()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(Layout::Rows)]
+    #[case(Layout::Columns)]
+    #[case(Layout::RightToLeft)]
+    #[case(Layout::TopToBottom)]
+    fn test_to_text_round_trip(#[case] layout: Layout) {
+        for read in [Layout::Rows, Layout::Columns, Layout::RightToLeft] {
+            let homework = Homework::parse(EXAMPLE, read).unwrap();
+            let text = homework.to_text(layout).unwrap();
+            let reread = Homework::parse(&text, layout).unwrap();
+            assert_eq!(contents(&reread), contents(&homework));
+            assert_eq!(reread.to_text(layout).unwrap(), text);
+        }
+    }
+
+    #[rstest]
+    fn test_to_text_expressions_round_trip() {
+        let homework = Homework::parse_expressions("2 -3\n^  *\n3 10\n+  -\n1  5").unwrap();
+        let text = homework.to_text(Layout::Expressions).unwrap();
+        assert_eq!(text, "2 -3\n^  *\n3 10\n+  -\n1  5\n");
+        assert_eq!(
+            contents(&Homework::parse_expressions(&text).unwrap()),
+            contents(&homework)
+        );
+    }
+
+    #[rstest]
+    #[case(Layout::Rows, "12 -4\n 3  7\n *  <\n")]
+    #[case(Layout::Columns, "13 -7\n2  4 \n*  < \n")]
+    #[case(Layout::RightToLeft, "7- 31\n 4  2\n<  * \n")]
+    #[case(Layout::TopToBottom, "12*\n3  \n   \n-4<\n7  \n")]
+    fn test_to_text(#[case] layout: Layout, #[case] expected: &str) {
+        let homework = Homework {
+            problems: vec![
+                Problem {
+                    values: vec![12, 3],
+                    operators: vec![Operation::Mult],
+                    span: (0, 2),
+                },
+                Problem {
+                    values: vec![-4, 7],
+                    operators: vec![Operation::Min],
+                    span: (3, 5),
+                },
+            ],
+        };
+        assert_eq!(homework.to_text(layout).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        Layout::Auto,
+        "1\n2\n+",
+        HomeworkError::UnrenderableLayout(Layout::Auto)
+    )]
+    #[case(
+        Layout::Expressions,
+        "1 2\n3 4\n5 6\n+ *",
+        HomeworkError::UnrenderableProblem { layout: Layout::Expressions, problem: 0 }
+    )]
+    #[case(
+        Layout::Rows,
+        "1 2\n* +\n3 4\n- /\n5 6",
+        HomeworkError::UnrenderableProblem { layout: Layout::Rows, problem: 0 }
+    )]
+    #[case(
+        Layout::Columns,
+        "1 2\n* +\n3 4\n- /\n5 6",
+        HomeworkError::UnrenderableProblem { layout: Layout::Columns, problem: 0 }
+    )]
+    fn test_to_text_errors(
+        #[case] layout: Layout,
+        #[case] expressions: &str,
+        #[case] expected: HomeworkError,
+    ) {
+        let homework = Homework::parse(expressions, Layout::Expressions)
+            .or_else(|_| Homework::parse(expressions, Layout::Rows))
+            .unwrap();
+        assert_eq!(homework.to_text(layout), Err(expected));
+    }
+
+    #[rstest]
+    fn test_to_text_uneven_rows() {
+        let homework = Homework::parse("1 2 \n3 45\n+ * ", Layout::Columns).unwrap();
+        assert_eq!(contents(&homework)[0].0, vec![13]);
+        assert_eq!(
+            homework.to_text(Layout::Rows),
+            Err(HomeworkError::UnrenderableProblem {
+                layout: Layout::Rows,
+                problem: 1
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_top_to_bottom() {
+        let worksheet = "123*\n45\n6\n\n328+\n64\n98";
+        let homework = Homework::parse(worksheet, Layout::TopToBottom).unwrap();
+        assert_eq!(
+            homework.problems,
+            vec![
+                Problem {
+                    values: vec![123, 45, 6],
+                    operators: vec![Operation::Mult],
+                    span: (0, 3),
+                },
+                Problem {
+                    values: vec![328, 64, 98],
+                    operators: vec![Operation::Add],
+                    span: (4, 7),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_auto_ambiguous() {
+        assert_eq!(
+            Homework::parse(EXAMPLE, Layout::Auto),
+            Err(HomeworkError::AmbiguousLayout(vec![
+                Layout::Rows,
+                Layout::Columns
+            ]))
+        );
+    }
+
+    #[rstest]
+    #[case("1 2\n345\n+  ", vec![vec![13, 4, 25]])]
+    #[case("1  \n 22\n333\n*  ", vec![vec![1, 22, 333]])]
+    fn test_auto(#[case] worksheet: &str, #[case] expected: Vec<Vec<isize>>) {
+        let homework = Homework::parse(worksheet, Layout::Auto).unwrap();
+        let values: Vec<Vec<isize>> = homework.problems.iter().map(|p| p.values.clone()).collect();
+        assert_eq!(values, expected);
+    }
+
+    #[rstest]
+    fn test_auto_unrecognised() {
+        assert_eq!(
+            Homework::parse("1 2\n3 4\n+  ", Layout::Auto),
+            Err(HomeworkError::UnrecognisedLayout)
+        );
     }
 }
@@ -1,18 +1,66 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
     ops::Add,
+    path::PathBuf,
 };
 
-use pyo3::prelude::*;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use pyo3::{
+    exceptions::{PyIndexError, PyValueError},
+    prelude::*,
+    types::PyInt,
+};
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 
+use crate::pnm;
+pub use timelines::{Choice, Timeline, TimelineIterator};
+use timelines::{SplitMix64, Timelines};
+
+mod timelines;
+
 #[pymodule(module = "aoc_2026.rs.day07")]
 pub fn day7(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<TachyonManifold>()?;
+    m.add_class::<Timeline>()?;
+    m.add_class::<Choice>()?;
 
     Ok(())
 }
 
+#[derive(PartialEq, Debug)]
+enum TachyonManifoldError {
+    /// Some beam goes round in a loop forever, so never ends its timeline
+    Cycle,
+    /// Beams have been combined, so their timelines are no longer counted
+    CombinedBeams,
+    NoSuchTimeline(BigUint),
+    NoTimelines,
+}
+
+impl Display for TachyonManifoldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cycle => write!(f, "Beams loop forever, so timelines never end"),
+            Self::CombinedBeams => {
+                write!(f, "Beams have been combined, so timelines can't be counted")
+            }
+            Self::NoSuchTimeline(index) => write!(f, "No timeline with index {index}"),
+            Self::NoTimelines => write!(f, "No timelines to sample from"),
+        }
+    }
+}
+
+impl From<TachyonManifoldError> for PyErr {
+    fn from(value: TachyonManifoldError) -> Self {
+        match value {
+            TachyonManifoldError::NoSuchTimeline(_) => PyIndexError::new_err(value.to_string()),
+            _ => PyValueError::new_err(value.to_string()),
+        }
+    }
+}
+
 #[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
 struct Coordinate(isize, isize);
 
@@ -24,20 +72,204 @@ impl Add<Coordinate> for Coordinate {
     }
 }
 
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn offset(self) -> Coordinate {
+        match self {
+            Self::Up => Coordinate(0, -1),
+            Self::Down => Coordinate(0, 1),
+            Self::Left => Coordinate(-1, 0),
+            Self::Right => Coordinate(1, 0),
+        }
+    }
+
+    /// Offsets to either side of a beam travelling in this direction, the
+    /// left or upper side first
+    fn sides(self) -> [Coordinate; 2] {
+        match self {
+            Self::Up | Self::Down => [Coordinate(-1, 0), Coordinate(1, 0)],
+            Self::Left | Self::Right => [Coordinate(0, -1), Coordinate(0, 1)],
+        }
+    }
+}
+
+/// Optical elements beams interact with
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Element {
+    /// ``^``, splitting a beam into two on either side of it, each meeting
+    /// whatever element is in the cell it lands on
+    Splitter,
+    /// ``/``, turning beams moving down to the left and up to the right, and
+    /// vice versa
+    ForwardMirror,
+    /// ``\``, turning beams moving down to the right and up to the left, and
+    /// vice versa
+    BackMirror,
+    /// ``#``, stopping any beam that reaches it
+    Absorber,
+    /// ``v``, sending every beam that reaches it downwards
+    Funnel,
+}
+
+impl Element {
+    fn parse(value: char) -> Option<Self> {
+        match value {
+            '^' => Some(Self::Splitter),
+            '/' => Some(Self::ForwardMirror),
+            '\\' => Some(Self::BackMirror),
+            '#' => Some(Self::Absorber),
+            'v' => Some(Self::Funnel),
+            _ => None,
+        }
+    }
+}
+
+/// A beam front, at a position and travelling in a direction
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+struct Beam {
+    position: Coordinate,
+    direction: Direction,
+}
+
+/// Where a beam goes after moving one step
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Target {
+    Beam(Beam),
+    /// The beam ends at an absorber, or just outside the manifold
+    End(Coordinate),
+}
+
+/// Beams to where they go after their next step, and the splitter they hit on
+/// the way, if any
+type BeamGraph = HashMap<Beam, (Option<Coordinate>, Vec<Target>)>;
+
+/// Beams in a graph ordered so each comes after every beam leading to it, or
+/// an error if some beams go round in a loop
+fn topological_order(graph: &BeamGraph) -> Result<Vec<Beam>, TachyonManifoldError> {
+    let mut incoming: HashMap<Beam, usize> = HashMap::new();
+    for (_, targets) in graph.values() {
+        for target in targets {
+            if let Target::Beam(next) = target {
+                *incoming.entry(*next).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ready: Vec<Beam> = graph
+        .keys()
+        .filter(|beam| !incoming.contains_key(beam))
+        .copied()
+        .collect();
+    let mut order = Vec::with_capacity(graph.len());
+    while let Some(beam) = ready.pop() {
+        order.push(beam);
+        for target in &graph[&beam].1 {
+            if let Target::Beam(next) = target {
+                let remaining = incoming.get_mut(next).unwrap();
+                *remaining -= 1;
+                if *remaining == 0 {
+                    ready.push(*next);
+                }
+            }
+        }
+    }
+    // Beams in a loop are always waiting on the one before them
+    if order.len() < graph.len() {
+        return Err(TachyonManifoldError::Cycle);
+    }
+
+    Ok(order)
+}
+
 #[gen_stub_pyclass]
 #[pyclass(module = "aoc_2025.rs.day07")]
 #[derive(PartialEq, Debug)]
 struct TachyonManifold {
-    /// Coordinates of the beam fronts to number of beams at that position
-    beam_fronts: HashMap<Coordinate, usize>,
+    /// Beam fronts to number of beams at that position and direction
+    beam_fronts: HashMap<Beam, BigUint>,
 
-    /// Coordinates of splitters
-    splitter_locations: HashSet<Coordinate>,
+    /// Coordinates of optical elements
+    elements: HashMap<Coordinate, Element>,
+
+    /// Number of columns in the manifold
+    #[pyo3(get)]
+    width: usize,
+
+    /// Number of rows in the manifold
+    #[pyo3(get)]
+    height: usize,
+
+    /// Coordinates where beams have ended, either at an absorber or just
+    /// outside the manifold, to number of beams that ended there
+    exits: HashMap<Coordinate, BigUint>,
+
+    /// Characters of the manifold as written, with short lines padded
+    grid: Vec<Vec<char>>,
+
+    /// Coordinates beams have passed through to number of beams that passed
+    visited: HashMap<Coordinate, BigUint>,
+
+    /// Whether propagating has combined beams at the same position, so beam
+    /// and exit counts no longer count timelines
+    combined: bool,
+
+    /// Whether beams from the fronts have been checked not to loop, which
+    /// stays true as they propagate
+    acyclic: bool,
+}
+
+/// Where timelines end up once every beam has left the manifold
+#[derive(PartialEq, Debug)]
+struct Outcome {
+    /// Number of splits, with beams reaching the same position combined
+    splits: usize,
+    /// Number of timelines reaching each splitter that's reached at all
+    splitters: HashMap<Coordinate, BigUint>,
+    /// Number of timelines leaving through the bottom of each column
+    bottom: Vec<BigUint>,
+    /// Number of timelines leaving through the top
+    top: BigUint,
+    /// Number of timelines leaving through the left side
+    left: BigUint,
+    /// Number of timelines leaving through the right side
+    right: BigUint,
+    /// Number of timelines stopped by absorbers
+    absorbed: BigUint,
+}
+
+impl Outcome {
+    /// Add timelines ending at a coordinate of a manifold with the given
+    /// height
+    fn end(&mut self, coord: Coordinate, height: usize, count: &BigUint) {
+        let total = if coord.0 < 0 {
+            &mut self.left
+        } else if coord.0 as usize >= self.bottom.len() {
+            &mut self.right
+        } else if coord.1 < 0 {
+            &mut self.top
+        } else if coord.1 as usize >= height {
+            &mut self.bottom[coord.0 as usize]
+        } else {
+            &mut self.absorbed
+        };
+        *total += count;
+    }
+
+    fn timelines(&self) -> BigUint {
+        self.bottom.iter().sum::<BigUint>() + &self.top + &self.left + &self.right + &self.absorbed
+    }
 }
 
 impl From<&str> for TachyonManifold {
     fn from(value: &str) -> Self {
-        let (beam_fronts, splitter_locations) = value
+        let (beam_fronts, elements) = value
             .lines()
             .enumerate()
             .flat_map(|(row, line)| {
@@ -47,77 +279,449 @@ impl From<&str> for TachyonManifold {
             })
             .fold(
                 (
-                    HashMap::<Coordinate, usize>::new(),
-                    HashSet::<Coordinate>::new(),
+                    HashMap::<Beam, BigUint>::new(),
+                    HashMap::<Coordinate, Element>::new(),
                 ),
-                |(mut beams, mut splitters), (coord, value)| {
+                |(mut beams, mut elements), (coord, value)| {
                     if value == 'S' {
-                        let entry = beams.entry(coord).or_insert(0);
-                        *entry += 1;
-                    } else if value == '^' {
-                        splitters.insert(coord);
+                        let entry = beams
+                            .entry(Beam {
+                                position: coord,
+                                direction: Direction::Down,
+                            })
+                            .or_default();
+                        *entry += 1u32;
+                    } else if let Some(element) = Element::parse(value) {
+                        elements.insert(coord, element);
                     }
-                    (beams, splitters)
+                    (beams, elements)
                 },
             );
 
+        let width = value
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let grid: Vec<Vec<char>> = value
+            .lines()
+            .map(|line| {
+                let mut chars: Vec<char> = line.chars().collect();
+                chars.resize(width, '.');
+                chars
+            })
+            .collect();
+        let mut visited: HashMap<Coordinate, BigUint> = HashMap::new();
+        for (beam, count) in &beam_fronts {
+            *visited.entry(beam.position).or_default() += count;
+        }
+
         Self {
             beam_fronts,
-            splitter_locations,
+            elements,
+            width,
+            height: grid.len(),
+            exits: HashMap::new(),
+            grid,
+            visited,
+            combined: false,
+            acyclic: false,
         }
     }
 }
 
-#[gen_stub_pymethods]
-#[pymethods]
 impl TachyonManifold {
-    #[new]
-    fn new(value: &str) -> Self {
-        Self::from(value)
+    /// Coordinates and characters of each row of the manifold
+    fn cells(&self) -> impl Iterator<Item = impl Iterator<Item = (Coordinate, char)> + '_> {
+        self.grid.iter().enumerate().map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(col, &c)| (Coordinate(col as isize, row as isize), c))
+        })
     }
 
-    /// Propagate the system one step, returning the numbers of splits that occurred.
-    /// Returns None if all the fronts have cleared all the splitters
-    fn propagate(&mut self, combine_beams: bool) -> Option<usize> {
-        if self.beam_fronts.keys().map(|front| front.1).min()
-            >= self.splitter_locations.iter().map(|loc| loc.1).max()
-        {
-            return None;
+    /// Greyscale level of each cell in row-major order. Cells no beam has
+    /// passed through are black, and the rest are lighter the more beams
+    /// passed through them, by their number of bits as numbers of timelines
+    /// grow exponentially.
+    fn heat_map_levels(&self) -> Vec<u8> {
+        let max_bits = self.visited.values().map(BigUint::bits).max().unwrap_or(0);
+        self.cells()
+            .flatten()
+            .map(|(coord, _)| match self.visited.get(&coord) {
+                Some(count) => (255 * count.bits() / max_bits.max(1)) as u8,
+                None => 0,
+            })
+            .collect()
+    }
+
+    /// Number of beams that have passed through each cell so far, by row
+    fn heat_map(&self) -> Vec<Vec<BigUint>> {
+        self.cells()
+            .map(|row| {
+                row.map(|(coord, _)| self.visited.get(&coord).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn contains(&self, coord: Coordinate) -> bool {
+        (0..self.width as isize).contains(&coord.0) && (0..self.height as isize).contains(&coord.1)
+    }
+
+    /// Where a beam arriving at a position goes, short of splitting. A beam
+    /// arriving at a splitter stays on it, to be split on its next step.
+    fn arrive(&self, position: Coordinate, direction: Direction) -> Target {
+        let turn = |direction| {
+            Target::Beam(Beam {
+                position,
+                direction,
+            })
+        };
+
+        match (self.elements.get(&position), direction) {
+            _ if !self.contains(position) => Target::End(position),
+            (Some(Element::Absorber), _) => Target::End(position),
+            (Some(Element::ForwardMirror), Direction::Down)
+            | (Some(Element::BackMirror), Direction::Up) => turn(Direction::Left),
+            (Some(Element::ForwardMirror), Direction::Up)
+            | (Some(Element::BackMirror), Direction::Down) => turn(Direction::Right),
+            (Some(Element::ForwardMirror), Direction::Left)
+            | (Some(Element::BackMirror), Direction::Right)
+            | (Some(Element::Funnel), _) => turn(Direction::Down),
+            (Some(Element::ForwardMirror), Direction::Right)
+            | (Some(Element::BackMirror), Direction::Left) => turn(Direction::Up),
+            (Some(Element::Splitter) | None, direction) => turn(direction),
         }
+    }
 
-        let unpropagated_fronts = self.beam_fronts.clone();
-        self.beam_fronts = HashMap::new();
+    /// Beams on either side of a splitter, each arriving at the cell it
+    /// lands on
+    fn split(
+        &self,
+        splitter: Coordinate,
+        direction: Direction,
+    ) -> (Option<Coordinate>, Vec<Target>) {
+        (
+            Some(splitter),
+            direction
+                .sides()
+                .iter()
+                .map(|&side| self.arrive(splitter + side, direction))
+                .collect(),
+        )
+    }
 
-        let mut total_splits = 0;
+    /// Where a beam goes after moving one step, and the splitter that split
+    /// it, if any. Beams split onto another splitter split again without
+    /// moving.
+    fn step(&self, beam: Beam) -> (Option<Coordinate>, Vec<Target>) {
+        if self.elements.get(&beam.position) == Some(&Element::Splitter) {
+            return self.split(beam.position, beam.direction);
+        }
+
+        let position = beam.position + beam.direction.offset();
+        if self.contains(position) && self.elements.get(&position) == Some(&Element::Splitter) {
+            self.split(position, beam.direction)
+        } else {
+            (None, vec![self.arrive(position, beam.direction)])
+        }
+    }
+
+    /// Every beam reachable from the current fronts, along with where it goes
+    /// after its next step and the splitter it hits on the way, if any
+    fn beam_graph(&self) -> BeamGraph {
+        let mut graph = HashMap::new();
+        let mut queue: VecDeque<Beam> = self.beam_fronts.keys().copied().collect();
+        while let Some(beam) = queue.pop_front() {
+            if graph.contains_key(&beam) {
+                continue;
+            }
+            let (splitter, targets) = self.step(beam);
+            queue.extend(targets.iter().filter_map(|target| match target {
+                Target::Beam(next) => Some(*next),
+                Target::End(_) => None,
+            }));
+            graph.insert(beam, (splitter, targets));
+        }
+
+        graph
+    }
+
+    /// Timelines from the current beam fronts
+    fn timelines(&self) -> Result<Timelines, TachyonManifoldError> {
+        if self.combined {
+            return Err(TachyonManifoldError::CombinedBeams);
+        }
+        Timelines::new(&self.beam_fronts, self.beam_graph())
+    }
+
+    /// Splitters reached by beams from the current fronts
+    fn reached_splitters(&self) -> HashSet<Coordinate> {
+        self.beam_graph()
+            .into_values()
+            .filter_map(|(splitter, _)| splitter)
+            .collect()
+    }
+
+    /// Move every beam front one step, returning the number of splits, or
+    /// None once every beam has ended
+    fn propagate(&mut self, combine_beams: bool) -> Result<Option<BigUint>, TachyonManifoldError> {
+        if self.beam_fronts.is_empty() {
+            return Ok(None);
+        }
+        if !self.acyclic {
+            topological_order(&self.beam_graph())?;
+            self.acyclic = true;
+        }
+
+        let unpropagated_fronts = std::mem::take(&mut self.beam_fronts);
+
+        let mut total_splits = BigUint::zero();
 
         for (beam_front, count) in unpropagated_fronts {
-            let new_coord = beam_front + Coordinate(0, 1);
-            if self.splitter_locations.contains(&new_coord) {
-                let left_entry = self
-                    .beam_fronts
-                    .entry(new_coord + Coordinate(-1, 0))
-                    .or_insert(0);
-                *left_entry += count;
-                let right_entry = self
-                    .beam_fronts
-                    .entry(new_coord + Coordinate(1, 0))
-                    .or_insert(0);
-                *right_entry += count;
-
-                total_splits += count;
-            } else {
-                let entry = self.beam_fronts.entry(new_coord).or_insert(0);
-                *entry += count;
+            let (splitter, targets) = self.step(beam_front);
+            if splitter.is_some() {
+                total_splits += &count;
+            }
+
+            for target in targets {
+                match target {
+                    Target::Beam(beam) => *self.beam_fronts.entry(beam).or_default() += &count,
+                    Target::End(coord) => *self.exits.entry(coord).or_default() += &count,
+                }
             }
         }
 
         if combine_beams {
-            self.beam_fronts
-                .iter_mut()
-                .for_each(|(_, value)| *value = 1);
+            for count in self.beam_fronts.values_mut() {
+                if !count.is_one() {
+                    self.combined = true;
+                    *count = BigUint::one();
+                }
+            }
+        }
+        for (beam, count) in &self.beam_fronts {
+            *self.visited.entry(beam.position).or_default() += count;
+        }
+
+        Ok(Some(total_splits))
+    }
+
+    /// Where the timelines of the beams that have ended and of the current
+    /// beam fronts end up. Passes the number of timelines along each beam
+    /// once every beam leading to it has been counted, so takes a single pass
+    /// however many timelines there are.
+    fn sweep(&self) -> Result<Outcome, TachyonManifoldError> {
+        if self.combined {
+            return Err(TachyonManifoldError::CombinedBeams);
+        }
+        let mut outcome = Outcome {
+            splits: 0,
+            splitters: HashMap::new(),
+            bottom: vec![BigUint::zero(); self.width],
+            top: BigUint::zero(),
+            left: BigUint::zero(),
+            right: BigUint::zero(),
+            absorbed: BigUint::zero(),
+        };
+        for (&exit, count) in &self.exits {
+            outcome.end(exit, self.height, count);
         }
 
-        Some(total_splits)
+        let graph = self.beam_graph();
+        let mut counts = self.beam_fronts.clone();
+        for beam in topological_order(&graph)? {
+            let count = counts.remove(&beam).unwrap_or_default();
+            let (splitter, targets) = &graph[&beam];
+            if let Some(splitter) = splitter {
+                outcome.splits += 1;
+                *outcome.splitters.entry(*splitter).or_default() += &count;
+            }
+            for target in targets {
+                match target {
+                    Target::Beam(next) => *counts.entry(*next).or_default() += &count,
+                    Target::End(coord) => outcome.end(*coord, self.height, &count),
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl TachyonManifold {
+    #[new]
+    fn new(value: &str) -> Self {
+        Self::from(value)
+    }
+
+    /// Propagate the system one step, returning the numbers of splits that occurred.
+    /// Returns None once every beam has ended, and raises an error if a beam
+    /// goes round in a loop. Once beams have been combined, counting
+    /// timelines raises an error.
+    #[pyo3(name = "propagate")]
+    fn py_propagate<'py>(
+        &mut self,
+        py: Python<'py>,
+        combine_beams: bool,
+    ) -> PyResult<Option<Bound<'py, PyInt>>> {
+        self.propagate(combine_beams)?
+            .map(|splits| splits.into_pyobject(py))
+            .transpose()
+    }
+
+    /// The manifold as written, with ``|`` in the empty cells beams have
+    /// passed through so far
+    fn render(&self) -> String {
+        self.cells()
+            .map(|row| {
+                row.map(|(coord, c)| match c {
+                    '.' if self.visited.contains_key(&coord) => '|',
+                    c => c,
+                })
+                .chain(['\n'])
+                .collect::<String>()
+            })
+            .collect()
+    }
+
+    /// Number of beams that have passed through each cell so far, by row.
+    /// Without combining beams, this is the number of timelines through each
+    /// cell.
+    #[pyo3(name = "heat_map")]
+    fn py_heat_map<'py>(&self, py: Python<'py>) -> PyResult<Vec<Vec<Bound<'py, PyInt>>>> {
+        self.heat_map()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|count| count.into_pyobject(py))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Save the number of beams that have passed through each cell so far as
+    /// a greyscale PGM image
+    fn save_pgm(&self, path: PathBuf) -> PyResult<()> {
+        Ok(pnm::write_pgm(
+            &path,
+            self.width,
+            self.height,
+            &self.heat_map_levels(),
+        )?)
+    }
+
+    /// Number of splits still to come before every beam has ended, with beams
+    /// reaching the same position combined into one
+    fn total_splits(&self) -> usize {
+        self.beam_graph()
+            .values()
+            .filter(|(splitter, _)| splitter.is_some())
+            .count()
+    }
+
+    /// Coordinates (column, row) of the splitters beams reach from the
+    /// current fronts
+    fn activated_splitters(&self) -> HashSet<(isize, isize)> {
+        self.reached_splitters()
+            .into_iter()
+            .map(|coord| (coord.0, coord.1))
+            .collect()
+    }
+
+    /// Coordinates (column, row) of the splitters beams reach from the
+    /// current fronts, to the number of timelines reaching each. Raises an
+    /// error if a beam goes round in a loop.
+    fn splitter_timelines<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<HashMap<(isize, isize), Bound<'py, PyInt>>> {
+        self.sweep()?
+            .splitters
+            .into_iter()
+            .map(|(coord, count)| Ok(((coord.0, coord.1), count.into_pyobject(py)?)))
+            .collect()
+    }
+
+    /// Timelines from the current beam fronts, in lexicographic order of where
+    /// they start, by row then column, then of the way they go at each
+    /// splitter, left first. Raises an error if a beam goes round in a loop.
+    #[pyo3(name = "timelines")]
+    fn py_timelines(&self) -> PyResult<TimelineIterator> {
+        Ok(TimelineIterator::from(self.timelines()?))
+    }
+
+    /// The timeline at the given index in the order of ``timelines``, found
+    /// without going through the timelines before it
+    fn nth_timeline(&self, n: &Bound<'_, PyInt>) -> PyResult<Timeline> {
+        let n: BigUint = n.extract()?;
+        Ok(self
+            .timelines()?
+            .nth(n.clone())
+            .ok_or(TachyonManifoldError::NoSuchTimeline(n))?)
+    }
+
+    /// Timelines chosen uniformly at random with replacement, the same ones
+    /// for the same seed
+    fn sample_timelines(&self, count: usize, seed: u64) -> PyResult<Vec<Timeline>> {
+        let timelines = self.timelines()?;
+        let mut rng = SplitMix64::new(seed);
+        Ok((0..count)
+            .map(|_| {
+                timelines
+                    .sample(&mut rng)
+                    .ok_or(TachyonManifoldError::NoTimelines)
+            })
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Coordinates (column, row) of the splitters no beam reaches from the
+    /// current fronts, which could be removed without changing anything
+    fn dead_splitters(&self) -> HashSet<(isize, isize)> {
+        let reached = self.reached_splitters();
+        self.elements
+            .iter()
+            .filter(|&(coord, &element)| element == Element::Splitter && !reached.contains(coord))
+            .map(|(coord, _)| (coord.0, coord.1))
+            .collect()
+    }
+
+    /// Number of timelines once every beam has ended, where each split
+    /// doubles the timelines of the beams it splits. Raises an error if a
+    /// beam goes round in a loop.
+    fn timeline_count<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.sweep()?.timelines().into_pyobject(py)
+    }
+
+    /// Number of timelines leaving through the bottom of each column
+    fn bottom_exits<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyInt>>> {
+        self.sweep()?
+            .bottom
+            .into_iter()
+            .map(|count| count.into_pyobject(py))
+            .collect()
+    }
+
+    /// Numbers of timelines leaving through the left and right sides
+    fn side_exits<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyInt>, Bound<'py, PyInt>)> {
+        let outcome = self.sweep()?;
+        Ok((
+            outcome.left.into_pyobject(py)?,
+            outcome.right.into_pyobject(py)?,
+        ))
+    }
+
+    /// Number of timelines leaving through the top
+    fn top_exits<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.sweep()?.top.into_pyobject(py)
+    }
+
+    /// Number of timelines stopped by absorbers
+    fn absorbed_count<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        self.sweep()?.absorbed.into_pyobject(py)
     }
 }
 
@@ -125,42 +729,344 @@ impl TachyonManifold {
 mod tests {
     use super::*;
     use rstest::*;
+    use std::hash::Hash;
+
+    const EXAMPLE: &str = include_str!("../../../data/test/d7");
+
+    fn counts<K: Hash + Eq, const N: usize>(entries: [(K, u32); N]) -> HashMap<K, BigUint> {
+        entries
+            .into_iter()
+            .map(|(key, count)| (key, BigUint::from(count)))
+            .collect()
+    }
+
+    /// A full triangle of splitters, doubling the timelines on every row
+    fn triangle(depth: usize) -> String {
+        let width = 2 * depth + 3;
+        let mut lines = vec![format!("{:>w$}", "S", w = depth + 2)];
+        for row in 0..depth {
+            let line: String = (0..width)
+                .map(|col: usize| {
+                    let offset = col.abs_diff(depth + 1);
+                    if offset <= row && (row - offset).is_multiple_of(2) {
+                        '^'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    fn down(col: isize, row: isize) -> Beam {
+        Beam {
+            position: Coordinate(col, row),
+            direction: Direction::Down,
+        }
+    }
+
+    #[rstest]
+    fn test_sweep() {
+        let outcome = TachyonManifold::from(EXAMPLE).sweep().unwrap();
+        assert_eq!(outcome.splits, 21);
+        assert_eq!(outcome.timelines(), BigUint::from(40u32));
+        assert_eq!(outcome.left, BigUint::zero());
+        assert_eq!(outcome.right, BigUint::zero());
+    }
+
+    #[rstest]
+    fn test_sweep_after_propagate() {
+        let mut manifold = TachyonManifold::from(EXAMPLE);
+        let expected = manifold.sweep().unwrap().bottom;
+        let mut splits = BigUint::zero();
+        for _ in 0..5 {
+            splits += manifold.propagate(false).unwrap().unwrap();
+        }
+        let outcome = manifold.sweep().unwrap();
+        assert_eq!(splits, BigUint::from(3u32));
+        assert_eq!(outcome.splits, 18);
+        assert_eq!(outcome.timelines(), BigUint::from(40u32));
+
+        while manifold.propagate(false).unwrap().is_some() {}
+        let outcome = manifold.sweep().unwrap();
+        assert_eq!(outcome.splits, 0);
+        assert_eq!(outcome.bottom, expected);
+    }
+
+    #[rstest]
+    fn test_sweep_exponential_timelines() {
+        let depth = 150;
+        let outcome = TachyonManifold::from(triangle(depth).as_str())
+            .sweep()
+            .unwrap();
+        assert_eq!(outcome.splits, depth * (depth + 1) / 2);
+        assert_eq!(outcome.timelines(), BigUint::from(2u32).pow(depth as u32));
+    }
+
+    #[rstest]
+    fn test_propagate_exponential_timelines() {
+        let depth = 100;
+        let mut manifold = TachyonManifold::from(triangle(depth).as_str());
+        let mut splits = BigUint::zero();
+        while let Some(step) = manifold.propagate(false).unwrap() {
+            splits += step;
+        }
+        let timelines = BigUint::from(2u32).pow(depth as u32);
+        assert_eq!(splits, &timelines - 1u32);
+
+        let heat_map = manifold.heat_map();
+        assert_eq!(heat_map.last().unwrap().iter().sum::<BigUint>(), timelines);
+        let levels = manifold.heat_map_levels();
+        assert_eq!(levels[depth + 1], 2);
+        assert_eq!(levels.iter().max(), Some(&255));
+    }
+
+    #[rstest]
+    #[case("S\n^\n.", 1, 0, 1, 1)]
+    #[case("S.\n^.\n..", 1, 1, 1, 0)]
+    #[case(".S\n.^\n..", 1, 1, 0, 1)]
+    #[case("S..\n.^.\n...", 0, 1, 0, 0)]
+    fn test_side_exits(
+        #[case] input: &str,
+        #[case] splits: usize,
+        #[case] bottom: u32,
+        #[case] left: u32,
+        #[case] right: u32,
+    ) {
+        let mut manifold = TachyonManifold::from(input);
+        let outcome = manifold.sweep().unwrap();
+        assert_eq!(outcome.splits, splits);
+        assert_eq!(
+            outcome.bottom.iter().sum::<BigUint>(),
+            BigUint::from(bottom)
+        );
+        assert_eq!(outcome.left, BigUint::from(left));
+        assert_eq!(outcome.right, BigUint::from(right));
+
+        while manifold.propagate(false).unwrap().is_some() {}
+        assert_eq!(
+            manifold.sweep(),
+            Ok(Outcome {
+                splits: 0,
+                splitters: HashMap::new(),
+                ..outcome
+            })
+        );
+    }
+
+    #[rstest]
+    // Turned right then up, out through the top
+    #[case("S.\n\\/\n..", vec![0, 0], 1, 0, 0, 0)]
+    // Turned left and out, or right and out
+    #[case(".S.\n./.\n...", vec![0, 0, 0], 0, 1, 0, 0)]
+    #[case(".S.\n.\\.\n...", vec![0, 0, 0], 0, 0, 1, 0)]
+    #[case("S\n#\n.", vec![0], 0, 0, 0, 1)]
+    // Two sources meeting at a funnel
+    #[case("S.S\n\\v/\n...", vec![0, 2, 0], 0, 0, 0, 0)]
+    // Turned right into a splitter, which splits it up and down
+    #[case("S.\n\\^\n..", vec![0, 0], 0, 0, 2, 0)]
+    // Split into an absorber, or out of the side
+    #[case("S.\n^#\n..", vec![0, 0], 0, 1, 0, 1)]
+    // Split onto mirrors, turning the beams out of the sides
+    #[case("..S.\n./^.\n....", vec![0, 0, 0, 1], 0, 1, 0, 0)]
+    #[case(".S..\n.^\\.\n....", vec![1, 0, 0, 0], 0, 0, 1, 0)]
+    // Turned right into a splitter, with the beam split downwards onto a
+    // funnel, which keeps it going down
+    #[case("S..\n\\.^\n..v\n...", vec![0, 0, 1], 0, 0, 1, 0)]
+    fn test_elements(
+        #[case] input: &str,
+        #[case] bottom: Vec<u32>,
+        #[case] top: u32,
+        #[case] left: u32,
+        #[case] right: u32,
+        #[case] absorbed: u32,
+    ) {
+        let mut manifold = TachyonManifold::from(input);
+        let outcome = manifold.sweep().unwrap();
+        assert_eq!(
+            outcome.bottom,
+            bottom.into_iter().map(BigUint::from).collect::<Vec<_>>()
+        );
+        assert_eq!(outcome.top, BigUint::from(top));
+        assert_eq!(outcome.left, BigUint::from(left));
+        assert_eq!(outcome.right, BigUint::from(right));
+        assert_eq!(outcome.absorbed, BigUint::from(absorbed));
+
+        while manifold.propagate(false).unwrap().is_some() {}
+        assert_eq!(
+            manifold.sweep(),
+            Ok(Outcome {
+                splits: 0,
+                splitters: HashMap::new(),
+                ..outcome
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_funnel_combines_beams() {
+        let mut manifold = TachyonManifold::from("S.S\n\\v/\n...");
+        assert_eq!(manifold.propagate(true), Ok(Some(BigUint::from(0u32))));
+        assert_eq!(manifold.propagate(true), Ok(Some(BigUint::from(0u32))));
+        assert_eq!(manifold.beam_fronts, counts([(down(1, 1), 1)]));
+    }
+
+    #[rstest]
+    fn test_combined_timelines() {
+        let mut manifold = TachyonManifold::from(EXAMPLE);
+        for _ in 0..12 {
+            manifold.propagate(true).unwrap();
+        }
+        assert!(manifold.combined);
+        assert_eq!(manifold.sweep(), Err(TachyonManifoldError::CombinedBeams));
+        assert!(manifold.timelines().is_err());
+
+        // Nothing to lose count of until beams meet
+        let mut manifold = TachyonManifold::from(EXAMPLE);
+        for _ in 0..3 {
+            manifold.propagate(true).unwrap();
+        }
+        assert!(!manifold.combined);
+        assert_eq!(manifold.sweep().unwrap().timelines(), BigUint::from(40u32));
+    }
+
+    #[rstest]
+    fn test_splitters() {
+        let manifold = TachyonManifold::from(EXAMPLE);
+        assert_eq!(manifold.reached_splitters().len(), 21);
+        assert_eq!(manifold.dead_splitters(), HashSet::from([(9, 14)]));
+
+        let splitters = manifold.sweep().unwrap().splitters;
+        assert_eq!(splitters.len(), 21);
+        assert_eq!(splitters[&Coordinate(7, 2)], BigUint::from(1u32));
+        assert_eq!(splitters[&Coordinate(7, 6)], BigUint::from(2u32));
+    }
+
+    #[rstest]
+    fn test_cycle() {
+        let mut manifold = TachyonManifold::from("/.\\\nS..\n\\./");
+        assert_eq!(manifold.sweep(), Err(TachyonManifoldError::Cycle));
+        assert_eq!(manifold.total_splits(), 0);
+        assert_eq!(manifold.reached_splitters(), HashSet::new());
+        assert_eq!(manifold.propagate(false), Err(TachyonManifoldError::Cycle));
+        assert_eq!(manifold.propagate(true), Err(TachyonManifoldError::Cycle));
+
+        // Neighbouring splitters split beams onto each other forever
+        let mut manifold = TachyonManifold::from("S.\n^^\n..");
+        assert_eq!(manifold.sweep(), Err(TachyonManifoldError::Cycle));
+        assert_eq!(manifold.reached_splitters().len(), 2);
+        assert_eq!(manifold.propagate(false), Err(TachyonManifoldError::Cycle));
+    }
+
+    #[rstest]
+    fn test_render() {
+        let mut manifold = TachyonManifold::from(EXAMPLE);
+        assert_eq!(manifold.render(), EXAMPLE);
+
+        while manifold.propagate(true).unwrap().is_some() {}
+        assert_eq!(
+            manifold.render(),
+            [
+                ".......S.......",
+                ".......|.......",
+                "......|^|......",
+                "......|.|......",
+                ".....|^|^|.....",
+                ".....|.|.|.....",
+                "....|^|^|^|....",
+                "....|.|.|.|....",
+                "...|^|^|||^|...",
+                "...|.|.|||.|...",
+                "..|^|^|||^|^|..",
+                "..|.|.|||.|.|..",
+                ".|^|||^||.||^|.",
+                ".|.|||.||.||.|.",
+                "|^|^|^|^|^|||^|",
+                "|.|.|.|.|.|||.|",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn test_heat_map() {
+        let mut manifold = TachyonManifold::from(EXAMPLE);
+        while manifold.propagate(false).unwrap().is_some() {}
+
+        let heat_map = manifold.heat_map();
+        let row = |counts: [u32; 15]| counts.map(BigUint::from);
+        assert_eq!(heat_map[0][7], BigUint::one());
+        assert_eq!(
+            heat_map[2],
+            row([0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            heat_map[4],
+            row([0, 0, 0, 0, 0, 1, 0, 2, 0, 1, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            heat_map.last().unwrap().iter().sum::<BigUint>(),
+            BigUint::from(40u32)
+        );
+
+        let levels = manifold.heat_map_levels();
+        assert_eq!(levels.len(), 15 * 16);
+        assert_eq!(levels[0], 0);
+        assert_eq!(levels.iter().max(), Some(&255));
+    }
 
     #[rstest]
     fn test_from_str() {
-        let test_input = ".......S.......\n...............\n.......^.......";
+        let test_input = ".......S.......\n...............\n.......^.....#.";
         assert_eq!(
             TachyonManifold::from(test_input),
             TachyonManifold {
-                beam_fronts: HashMap::from([(Coordinate(7, 0), 1)]),
-                splitter_locations: HashSet::from([Coordinate(7, 2)]),
+                beam_fronts: counts([(down(7, 0), 1)]),
+                elements: HashMap::from([
+                    (Coordinate(7, 2), Element::Splitter),
+                    (Coordinate(13, 2), Element::Absorber)
+                ]),
+                width: 15,
+                height: 3,
+                exits: HashMap::new(),
+                grid: test_input
+                    .lines()
+                    .map(|line| line.chars().collect())
+                    .collect(),
+                visited: counts([(Coordinate(7, 0), 1)]),
+                combined: false,
+                acyclic: false,
             }
         );
     }
 
     #[rstest]
     fn test_propagate() {
-        let mut manifold = TachyonManifold {
-            beam_fronts: HashMap::from([(Coordinate(7, 0), 1)]),
-            splitter_locations: HashSet::from([Coordinate(7, 2)]),
-        };
+        let mut manifold =
+            TachyonManifold::from(".......S.......\n...............\n.......^.......");
 
-        assert_eq!(manifold.propagate(true), Some(0));
+        assert_eq!(manifold.propagate(true), Ok(Some(BigUint::from(0u32))));
 
-        assert_eq!(manifold.beam_fronts, HashMap::from([(Coordinate(7, 1), 1)]),);
+        assert_eq!(manifold.beam_fronts, counts([(down(7, 1), 1)]),);
 
-        assert_eq!(manifold.propagate(true), Some(1));
+        assert_eq!(manifold.propagate(true), Ok(Some(BigUint::from(1u32))));
 
         assert_eq!(
             manifold.beam_fronts,
-            HashMap::from([(Coordinate(6, 2), 1), (Coordinate(8, 2), 1)]),
+            counts([(down(6, 2), 1), (down(8, 2), 1)]),
         );
 
-        assert_eq!(manifold.propagate(true), None);
+        assert_eq!(manifold.propagate(true), Ok(Some(BigUint::from(0u32))));
 
+        assert_eq!(manifold.beam_fronts, HashMap::new());
         assert_eq!(
-            manifold.beam_fronts,
-            HashMap::from([(Coordinate(6, 2), 1), (Coordinate(8, 2), 1)]),
+            manifold.exits,
+            counts([(Coordinate(6, 3), 1), (Coordinate(8, 3), 1)]),
         );
+
+        assert_eq!(manifold.propagate(true), Ok(None));
     }
 }
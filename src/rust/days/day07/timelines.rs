@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+
+use super::{topological_order, Beam, BeamGraph, TachyonManifoldError, Target};
+
+/// Which way a beam went at a splitter
+#[gen_stub_pyclass_enum]
+#[pyclass(eq, eq_int, module = "aoc_2025.rs.day07")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Choice {
+    /// Left of the splitter, or above it for a beam moving sideways
+    Left,
+    /// Right of the splitter, or below it for a beam moving sideways
+    Right,
+}
+
+/// A single path a beam takes from a beam front until it ends
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day07")]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Timeline {
+    /// Coordinates (column, row) of the beam front the timeline starts from
+    #[pyo3(get)]
+    start: (isize, isize),
+
+    /// Which way the beam went at each splitter it reached, in order
+    #[pyo3(get)]
+    choices: Vec<Choice>,
+
+    /// Coordinates (column, row) where the timeline ends, at an absorber or
+    /// just outside the manifold
+    #[pyo3(get)]
+    end: (isize, isize),
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Timeline {
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Timeline(start={:?}, choices={:?}, end={:?})",
+            self.start, self.choices, self.end
+        )
+    }
+}
+
+/// Timelines from a set of beam fronts, numbered in lexicographic order of
+/// where they start, by row then column, then of their choices, left first.
+/// A front holding several beams starts the same timelines once for each.
+pub(super) struct Timelines {
+    /// Beam fronts in reading order, with the number of beams at each
+    starts: Vec<(Beam, BigUint)>,
+    graph: BeamGraph,
+    /// Number of timelines following on from each beam in the graph
+    counts: HashMap<Beam, BigUint>,
+    len: BigUint,
+}
+
+impl Timelines {
+    pub(super) fn new(
+        fronts: &HashMap<Beam, BigUint>,
+        graph: BeamGraph,
+    ) -> Result<Self, TachyonManifoldError> {
+        let mut counts: HashMap<Beam, BigUint> = HashMap::new();
+        for beam in topological_order(&graph)?.into_iter().rev() {
+            let count = graph[&beam]
+                .1
+                .iter()
+                .map(|target| match target {
+                    Target::Beam(next) => counts[next].clone(),
+                    Target::End(_) => BigUint::one(),
+                })
+                .sum();
+            counts.insert(beam, count);
+        }
+
+        let mut starts: Vec<(Beam, BigUint)> = fronts
+            .iter()
+            .map(|(&beam, count)| (beam, count.clone()))
+            .collect();
+        starts.sort_by_key(|(beam, _)| (beam.position.1, beam.position.0, beam.direction));
+        let len = starts
+            .iter()
+            .map(|(beam, count)| &counts[beam] * count)
+            .sum();
+
+        Ok(Self {
+            starts,
+            graph,
+            counts,
+            len,
+        })
+    }
+
+    fn count(&self, target: &Target) -> BigUint {
+        match target {
+            Target::Beam(beam) => self.counts[beam].clone(),
+            Target::End(_) => BigUint::one(),
+        }
+    }
+
+    /// The timeline with the given index, following the choice at each
+    /// splitter whose timelines cover the index
+    pub(super) fn nth(&self, mut n: BigUint) -> Option<Timeline> {
+        let mut start = None;
+        for (beam, copies) in &self.starts {
+            let block = &self.counts[beam] * copies;
+            if n < block {
+                n /= copies;
+                start = Some(*beam);
+                break;
+            }
+            n -= block;
+        }
+        let start = start?;
+
+        let mut choices = Vec::new();
+        let mut beam = start;
+        loop {
+            let target = match self.graph[&beam].1.as_slice() {
+                [left, right] => {
+                    let left_count = self.count(left);
+                    if n < left_count {
+                        choices.push(Choice::Left);
+                        left
+                    } else {
+                        n -= left_count;
+                        choices.push(Choice::Right);
+                        right
+                    }
+                }
+                targets => &targets[0],
+            };
+            match *target {
+                Target::Beam(next) => beam = next,
+                Target::End(end) => {
+                    return Some(Timeline {
+                        start: (start.position.0, start.position.1),
+                        choices,
+                        end: (end.0, end.1),
+                    })
+                }
+            }
+        }
+    }
+
+    /// A timeline chosen uniformly at random, if there are any
+    pub(super) fn sample(&self, rng: &mut SplitMix64) -> Option<Timeline> {
+        if self.len.is_zero() {
+            return None;
+        }
+        self.nth(rng.below(&self.len))
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(module = "aoc_2025.rs.day07")]
+pub struct TimelineIterator {
+    timelines: Timelines,
+    index: BigUint,
+}
+
+impl From<Timelines> for TimelineIterator {
+    fn from(timelines: Timelines) -> Self {
+        Self {
+            timelines,
+            index: BigUint::zero(),
+        }
+    }
+}
+
+impl Iterator for TimelineIterator {
+    type Item = Timeline;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let timeline = self.timelines.nth(self.index.clone())?;
+        self.index += 1u32;
+        Some(timeline)
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl TimelineIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Timeline> {
+        self.next()
+    }
+}
+
+/// SplitMix64 pseudorandom generator, which is small and plenty good enough
+/// for picking timelines reproducibly
+pub(super) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly random integer below a non-zero bound, drawing as many bits
+    /// as the bound has until the result is below it
+    fn below(&mut self, bound: &BigUint) -> BigUint {
+        let bits = bound.bits();
+        let words = bits.div_ceil(64);
+        loop {
+            let bytes: Vec<u8> = (0..words)
+                .flat_map(|_| self.next_u64().to_le_bytes())
+                .collect();
+            let candidate = BigUint::from_bytes_le(&bytes) >> (words * 64 - bits);
+            if &candidate < bound {
+                return candidate;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::TachyonManifold;
+    use super::*;
+    use rstest::*;
+
+    const EXAMPLE: &str = include_str!("../../../../data/test/d7");
+
+    fn timelines(input: &str) -> Timelines {
+        TachyonManifold::from(input).timelines().unwrap()
+    }
+
+    #[rstest]
+    fn test_iterate_in_order() {
+        let all: Vec<Timeline> = TimelineIterator::from(timelines(EXAMPLE)).collect();
+        assert_eq!(all.len(), 40);
+        assert!(all.windows(2).all(|pair| pair[0].choices < pair[1].choices));
+        assert_eq!(all[0].choices, vec![Choice::Left; 7]);
+        assert_eq!(all[0].end, (0, 16));
+        assert_eq!(all[39].end, (14, 16));
+
+        // Every timeline ends somewhere along the bottom
+        let manifold = TachyonManifold::from(EXAMPLE);
+        let mut bottom = vec![BigUint::zero(); manifold.width];
+        for timeline in &all {
+            bottom[timeline.end.0 as usize] += 1u32;
+        }
+        assert_eq!(bottom, manifold.sweep().unwrap().bottom);
+    }
+
+    #[rstest]
+    fn test_nth() {
+        let example = timelines(EXAMPLE);
+        assert_eq!(example.len, BigUint::from(40u32));
+        assert_eq!(
+            example.nth(BigUint::from(7u32)),
+            TimelineIterator::from(timelines(EXAMPLE)).nth(7)
+        );
+        assert_eq!(example.nth(BigUint::from(40u32)), None);
+
+        // The last of the 2^150 timelines through a triangle of splitters
+        let depth = 150;
+        let mut lines = vec![format!("{:>w$}", "S", w = depth + 1)];
+        for row in 0..depth {
+            lines.push(
+                (0..2 * depth + 1)
+                    .map(|col: usize| {
+                        let offset = col.abs_diff(depth);
+                        if offset <= row && (row - offset).is_multiple_of(2) {
+                            '^'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        let triangle = timelines(&lines.join("\n"));
+        let last = triangle.nth(BigUint::from(2u32).pow(depth as u32) - 1u32);
+        assert_eq!(last.unwrap().choices, vec![Choice::Right; depth]);
+    }
+
+    #[rstest]
+    fn test_multiple_sources() {
+        // Both sources meet at the funnel, then the splitter
+        let all: Vec<Timeline> =
+            TimelineIterator::from(timelines("S.S\n\\v/\n...\n.^.\n...")).collect();
+        let summary: Vec<((isize, isize), Vec<Choice>)> = all
+            .into_iter()
+            .map(|timeline| (timeline.start, timeline.choices))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ((0, 0), vec![Choice::Left]),
+                ((0, 0), vec![Choice::Right]),
+                ((2, 0), vec![Choice::Left]),
+                ((2, 0), vec![Choice::Right]),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_repeated_beams() {
+        let mut manifold = TachyonManifold::from("S\n^\n.");
+        manifold
+            .beam_fronts
+            .values_mut()
+            .for_each(|count| *count = BigUint::from(2u32));
+        let all = TimelineIterator::from(manifold.timelines().unwrap());
+        let choices: Vec<Vec<Choice>> = all.map(|timeline| timeline.choices).collect();
+        assert_eq!(
+            choices,
+            vec![
+                vec![Choice::Left],
+                vec![Choice::Left],
+                vec![Choice::Right],
+                vec![Choice::Right],
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_sample() {
+        let example = timelines(EXAMPLE);
+        let sample = |seed| {
+            let mut rng = SplitMix64::new(seed);
+            (0..20)
+                .map(|_| example.sample(&mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+
+        let pair = timelines("S\n^\n.");
+        let mut rng = SplitMix64::new(0);
+        let lefts = (0..1000)
+            .filter(|_| pair.sample(&mut rng).unwrap().choices == [Choice::Left])
+            .count();
+        assert!((400..600).contains(&lefts));
+
+        assert_eq!(timelines("...").sample(&mut rng), None);
+    }
+
+    #[rstest]
+    fn test_split_mix() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+
+        let bound = BigUint::from(2u32).pow(100) + 1u32;
+        assert!((0..100).all(|_| rng.below(&bound) < bound));
+        assert!((0..100).all(|_| rng.below(&BigUint::one()).is_zero()));
+    }
+}
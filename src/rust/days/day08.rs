@@ -142,7 +142,7 @@ impl Network {
             .filter(|((from, to), _)| from < to)
             .filter(|(coord, _)| !self.connections.contains(coord))
             .collect::<Vec<_>>();
-        available_connections.sort_by(|(_, a), (_, b)| a.cmp(b));
+        available_connections.sort_by_key(|&(_, cost)| cost);
 
         available_connections
             .into_iter()
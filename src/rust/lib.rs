@@ -1,6 +1,7 @@
 use pyo3::{prelude::*, wrap_pymodule};
 use pyo3_stub_gen::{define_stub_info_gatherer, derive::gen_stub_pyfunction};
 mod days;
+mod pnm;
 
 // Base module unfortunately requires some content to generate __init__.pyi
 // See https://github.com/Jij-Inc/pyo3-stub-gen/issues/107
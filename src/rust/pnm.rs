@@ -0,0 +1,70 @@
+//! Minimal writers for binary Netpbm images, so solutions can dump
+//! visualisations without pulling in an image crate.
+
+use std::{fs, io, path::Path};
+
+/// Encode a greyscale image as a binary PGM (P5).
+///
+/// ``pixels`` are in row-major order with one byte per pixel.
+pub fn pgm_bytes(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    assert_eq!(
+        pixels.len(),
+        width * height,
+        "Pixel count doesn't match size"
+    );
+
+    let mut bytes = format!("P5\n{width} {height}\n255\n").into_bytes();
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+/// Encode a colour image as a binary PPM (P6).
+///
+/// ``pixels`` are in row-major order as (red, green, blue).
+pub fn ppm_bytes(width: usize, height: usize, pixels: &[[u8; 3]]) -> Vec<u8> {
+    assert_eq!(
+        pixels.len(),
+        width * height,
+        "Pixel count doesn't match size"
+    );
+
+    let mut bytes = format!("P6\n{width} {height}\n255\n").into_bytes();
+    bytes.extend(pixels.iter().flatten());
+    bytes
+}
+
+pub fn write_pgm(path: &Path, width: usize, height: usize, pixels: &[u8]) -> io::Result<()> {
+    fs::write(path, pgm_bytes(width, height, pixels))
+}
+
+pub fn write_ppm(path: &Path, width: usize, height: usize, pixels: &[[u8; 3]]) -> io::Result<()> {
+    fs::write(path, ppm_bytes(width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    fn test_pgm_bytes() {
+        assert_eq!(
+            pgm_bytes(3, 1, &[0, 128, 255]),
+            b"P5\n3 1\n255\n\x00\x80\xff".to_vec()
+        );
+    }
+
+    #[rstest]
+    fn test_ppm_bytes() {
+        assert_eq!(
+            ppm_bytes(1, 2, &[[1, 2, 3], [4, 5, 6]]),
+            b"P6\n1 2\n255\n\x01\x02\x03\x04\x05\x06".to_vec()
+        );
+    }
+
+    #[rstest]
+    #[should_panic]
+    fn test_pgm_bytes_wrong_size() {
+        pgm_bytes(2, 2, &[0, 0, 0]);
+    }
+}
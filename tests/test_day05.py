@@ -1,4 +1,5 @@
 from aoc_2025.day05 import day05_p1, day05_p2
+from aoc_2025.rs.day05 import KitchenIMS
 
 
 def test_part1(test_data):
@@ -7,3 +8,11 @@ def test_part1(test_data):
 
 def test_part2(test_data):
     assert day05_p2(test_data("d5")) == 14
+
+
+def test_round_trip(test_data):
+    puzzle_input = test_data("d5")
+    ims = KitchenIMS(puzzle_input)
+
+    assert ims.to_text() == puzzle_input
+    assert KitchenIMS.from_json(ims.to_json()).to_text() == puzzle_input
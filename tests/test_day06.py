@@ -1,4 +1,5 @@
 from aoc_2025.day06 import day06_p1, day06_p2
+from aoc_2025.rs.day06 import Homework, Layout
 
 
 def test_part1(test_data):
@@ -7,3 +8,12 @@ def test_part1(test_data):
 
 def test_part2(test_data):
     assert day06_p2(test_data("d6")) == 3263827
+
+
+def test_to_text_round_trip(test_data):
+    homework = Homework.read_right(test_data("d6"))
+
+    for layout in (Layout.Rows, Layout.Columns, Layout.TopToBottom):
+        reread = Homework.parse(homework.to_text(layout), layout)
+        assert [p.values for p in reread] == [p.values for p in homework]
+        assert reread.grand_total() == homework.grand_total()
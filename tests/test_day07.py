@@ -1,4 +1,7 @@
+import pytest
+
 from aoc_2025.day07 import day07_p1, day07_p2
+from aoc_2025.rs.day07 import TachyonManifold
 
 
 def test_part1(test_data):
@@ -7,3 +10,38 @@ def test_part1(test_data):
 
 def test_part2(test_data):
     assert day07_p2(test_data("d7")) == 40
+
+
+def test_exits(test_data):
+    manifold = TachyonManifold(test_data("d7"))
+
+    assert len(manifold.bottom_exits()) == manifold.width
+    assert sum(manifold.bottom_exits()) == manifold.timeline_count()
+    assert manifold.side_exits() == (0, 0)
+
+
+def test_splitters(test_data):
+    manifold = TachyonManifold(test_data("d7"))
+
+    assert len(manifold.activated_splitters()) == manifold.total_splits()
+    assert manifold.dead_splitters() == {(9, 14)}
+    assert manifold.splitter_timelines()[(7, 2)] == 1
+
+
+def test_timelines(test_data):
+    manifold = TachyonManifold(test_data("d7"))
+    timelines = list(manifold.timelines())
+
+    assert len(timelines) == manifold.timeline_count()
+    assert manifold.nth_timeline(7) == timelines[7]
+    assert manifold.sample_timelines(5, seed=1) == manifold.sample_timelines(5, seed=1)
+    assert all(t in timelines for t in manifold.sample_timelines(5, seed=2))
+
+
+def test_combined_timelines(test_data):
+    manifold = TachyonManifold(test_data("d7"))
+    for _ in range(12):
+        manifold.propagate(True)
+
+    with pytest.raises(ValueError):
+        manifold.timeline_count()